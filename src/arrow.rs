@@ -0,0 +1,188 @@
+//! Conversions between `JavaString` columns and Arrow `StringArray`s.
+//!
+//! `StringArray` here is the same type as `arrow::array::StringArray`, so the
+//! results can go straight into a record batch.
+
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use arrow_array::{Array, StringArray};
+use arrow_buffer::{Buffer, NullBuffer, OffsetBuffer, ScalarBuffer};
+use core::convert::TryFrom;
+
+/// Builds a `StringArray` out of `values`, copying each string's bytes once
+/// into a single values buffer.
+///
+/// # Panics
+///
+/// Panics if the total length of `values` doesn't fit in an `i32` offset.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// # use jstring::JavaString;
+/// use jstring::arrow::to_string_array;
+///
+/// let array = to_string_array(&[JavaString::from("foo"), JavaString::from("bar")]);
+///
+/// assert_eq!(array.value(1), "bar");
+/// ```
+pub fn to_string_array(values: &[JavaString]) -> StringArray {
+    build_string_array(values.iter().map(Some), values.len(), None)
+}
+
+/// Builds a `StringArray` out of `values`, with a null for every `None`.
+///
+/// # Panics
+///
+/// Panics if the total length of `values` doesn't fit in an `i32` offset.
+pub fn to_string_array_with_nulls(values: &[Option<JavaString>]) -> StringArray {
+    let nulls = NullBuffer::from(values.iter().map(Option::is_some).collect::<Vec<_>>());
+    build_string_array(values.iter().map(Option::as_ref), values.len(), Some(nulls))
+}
+
+fn build_string_array<'a>(
+    values: impl Iterator<Item = Option<&'a JavaString>> + Clone,
+    count: usize,
+    nulls: Option<NullBuffer>,
+) -> StringArray {
+    let total: usize = values.clone().flatten().map(|value| value.len()).sum();
+    let offset = |len: usize| i32::try_from(len).expect("StringArray offset overflow");
+    offset(total);
+
+    let mut offsets = Vec::with_capacity(count + 1);
+    let mut bytes = Vec::with_capacity(total);
+    offsets.push(0);
+    for value in values {
+        if let Some(value) = value {
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        offsets.push(offset(bytes.len()));
+    }
+
+    let nulls = nulls.filter(|nulls| nulls.null_count() > 0);
+    let offsets = OffsetBuffer::new(ScalarBuffer::from(offsets));
+
+    // Safety: the offsets are monotonic and end at `bytes.len()`, and every
+    // range between them is a whole `JavaString`, so it's valid UTF-8.
+    unsafe { StringArray::new_unchecked(offsets, Buffer::from_vec(bytes), nulls) }
+}
+
+/// Converts every slot of `array` into a `JavaString`, with `None` for nulls.
+/// Values short enough to be interned don't allocate.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use arrow_array::StringArray;
+/// use jstring::arrow::from_string_array;
+///
+/// let array = StringArray::from(vec![Some("foo"), None]);
+///
+/// assert_eq!(from_string_array(&array), [Some("foo".into()), None]);
+/// ```
+pub fn from_string_array(array: &StringArray) -> Vec<Option<JavaString>> {
+    array
+        .iter()
+        .map(|value| value.map(to_java_string))
+        .collect()
+}
+
+/// Converts every slot of `array` into a `JavaString`. Returns `None` if
+/// `array` contains any nulls; use [`from_string_array`] for those.
+///
+/// [`from_string_array`]: fn.from_string_array.html
+pub fn from_string_array_no_nulls(array: &StringArray) -> Option<Vec<JavaString>> {
+    if array.null_count() > 0 {
+        return None;
+    }
+
+    Some(
+        (0..array.len())
+            .map(|idx| to_java_string(array.value(idx)))
+            .collect(),
+    )
+}
+
+fn to_java_string(value: &str) -> JavaString {
+    JavaString {
+        data: RawJavaString::from_bytes(value.as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let values: Vec<JavaString> = ["", "short", "", "a value that lives on the heap"]
+            .iter()
+            .map(|&value| value.into())
+            .collect();
+
+        let array = to_string_array(&values);
+        assert_eq!(array.len(), 4);
+        assert_eq!(array.null_count(), 0);
+        assert_eq!(from_string_array_no_nulls(&array).unwrap(), values);
+
+        let empty = to_string_array(&[]);
+        assert_eq!(empty.len(), 0);
+        assert!(from_string_array_no_nulls(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn round_trip_with_nulls() {
+        let values = vec![
+            Some(JavaString::from("short")),
+            None,
+            Some(JavaString::new()),
+            None,
+            Some(JavaString::from("a value that lives on the heap")),
+        ];
+
+        let array = to_string_array_with_nulls(&values);
+        assert_eq!(array.null_count(), 2);
+        assert!(array.is_null(1));
+        assert!(array.is_valid(2));
+        assert_eq!(from_string_array(&array), values);
+        assert!(from_string_array_no_nulls(&array).is_none());
+    }
+
+    #[test]
+    fn multibyte_offsets() {
+        let values = [
+            JavaString::from("é"),
+            JavaString::from("💖€"),
+            JavaString::from("a"),
+        ];
+        let array = to_string_array(&values);
+
+        assert_eq!(array.value_offsets(), [0, 2, 9, 10]);
+        assert_eq!(array.value(1), "💖€");
+    }
+
+    #[test]
+    fn large_column_matches_string_path() {
+        let count = if cfg!(miri) { 500 } else { 10_000 };
+        let strings: Vec<String> = (0..count).map(|idx| "ab💖".repeat(idx % 13)).collect();
+        let values: Vec<JavaString> = strings.iter().map(|s| s.as_str().into()).collect();
+
+        let expected = StringArray::from(strings.clone());
+        let array = to_string_array(&values);
+        assert_eq!(array, expected);
+
+        let round_tripped = from_string_array_no_nulls(&expected).unwrap();
+        assert_eq!(round_tripped, values);
+        for value in &round_tripped {
+            assert_eq!(
+                value.data.is_interned(),
+                value.len() <= RawJavaString::max_intern_len()
+            );
+        }
+    }
+}
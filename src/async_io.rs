@@ -0,0 +1,200 @@
+//! Building `JavaString`s from tokio's async readers.
+//!
+//! Input is validated as it arrives, so invalid UTF-8 is reported as soon as
+//! it's read instead of after the whole stream has been buffered.
+
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt};
+
+/// How many bytes to make room for before each read.
+const CHUNK_SIZE: usize = 4096;
+
+impl JavaString {
+    /// Reads `reader` to the end, and returns its contents as a `JavaString`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` as soon as the stream contains
+    /// invalid UTF-8, or if it ends in the middle of a character. Errors from
+    /// `reader` are passed through.
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> io::Result<JavaString> {
+        read_validated(reader, None).await
+    }
+
+    /// Reads `reader` to the end, and returns its contents as a `JavaString`.
+    /// No more than `max_bytes + 1` bytes are ever read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` if the stream is longer than
+    /// `max_bytes`, as soon as it contains invalid UTF-8, or if it ends in the
+    /// middle of a character. Errors from `reader` are passed through.
+    pub async fn from_async_reader_limited<R: AsyncRead + Unpin>(
+        reader: R,
+        max_bytes: usize,
+    ) -> io::Result<JavaString> {
+        read_validated(reader, Some(max_bytes)).await
+    }
+}
+
+async fn read_validated<R: AsyncRead + Unpin>(
+    reader: R,
+    max_bytes: Option<usize>,
+) -> io::Result<JavaString> {
+    let limit = max_bytes.map_or(u64::MAX, |max| (max as u64).saturating_add(1));
+    let mut reader = reader.take(limit);
+    let mut bytes = Vec::new();
+    let mut validated = 0;
+
+    loop {
+        bytes.reserve(CHUNK_SIZE);
+        if reader.read_buf(&mut bytes).await? == 0 {
+            break;
+        }
+
+        if max_bytes.is_some_and(|max| bytes.len() > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream is longer than the size limit",
+            ));
+        }
+
+        // Only the bytes after the last complete character need checking.
+        match core::str::from_utf8(&bytes[validated..]) {
+            Ok(_) => validated = bytes.len(),
+            Err(e) if e.error_len().is_none() => validated += e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+
+    if validated != bytes.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream ended in the middle of a character",
+        ));
+    }
+
+    Ok(JavaString {
+        data: RawJavaString::from_byte_vec(bytes),
+    })
+}
+
+/// Reads the next line from `reader`, without its `\n` or `\r\n` ending.
+/// Returns `None` once `reader` has reached its end.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidData` if the line isn't valid UTF-8. The
+/// line is consumed either way, so the next call moves on to the next line.
+pub async fn read_jline<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<JavaString>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+
+    JavaString::from_utf8_mut(&mut line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tokio::io::{duplex, AsyncWriteExt, BufReader};
+
+    #[tokio::test]
+    async fn chunks_split_characters() {
+        // A two byte buffer means every multi-byte character arrives in pieces.
+        let (mut writer, reader) = duplex(2);
+        let text = "héllo 💖 wörld, this lives on the heap €";
+        tokio::spawn(async move { writer.write_all(text.as_bytes()).await });
+
+        let string = JavaString::from_async_reader(reader).await.unwrap();
+        assert_eq!(string, text);
+
+        let (mut writer, reader) = duplex(3);
+        tokio::spawn(async move { writer.write_all("💖é".as_bytes()).await });
+
+        let string = JavaString::from_async_reader(reader).await.unwrap();
+        assert_eq!(string, "💖é");
+        assert!(string.data.is_interned());
+    }
+
+    #[tokio::test]
+    async fn invalid_data_fails_early() {
+        let (mut writer, reader) = duplex(64);
+        writer.write_all(b"abc\xffdef").await.unwrap();
+
+        // The writer is still open, so this only returns because of the bad byte.
+        let err = JavaString::from_async_reader(reader).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        drop(writer);
+    }
+
+    #[tokio::test]
+    async fn size_limit() {
+        let string = JavaString::from_async_reader_limited(&b"12345"[..], 5)
+            .await
+            .unwrap();
+        assert_eq!(string, "12345");
+
+        let err = JavaString::from_async_reader_limited(&b"123456"[..], 5)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // The limit holds even if the stream never ends.
+        let (mut writer, reader) = duplex(64);
+        writer.write_all(&[b'a'; 32]).await.unwrap();
+        let err = JavaString::from_async_reader_limited(reader, 16)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // The largest limit reads everything instead of overflowing.
+        let string = JavaString::from_async_reader_limited(&b"12345"[..], usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(string, "12345");
+    }
+
+    #[tokio::test]
+    async fn eof_mid_character() {
+        let (mut writer, reader) = duplex(64);
+        writer.write_all(&"ab💖".as_bytes()[0..4]).await.unwrap();
+        drop(writer);
+
+        let err = JavaString::from_async_reader(reader).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn read_lines() {
+        let (mut writer, reader) = duplex(4);
+        let text = [
+            &b"first\r\nsecond "[..],
+            "💖".as_bytes(),
+            b"\n\r\nbad \xff\nlast",
+        ]
+        .concat();
+        tokio::spawn(async move { writer.write_all(&text).await });
+
+        let mut reader = BufReader::new(reader);
+        assert_eq!(read_jline(&mut reader).await.unwrap().unwrap(), "first");
+        assert_eq!(read_jline(&mut reader).await.unwrap().unwrap(), "second 💖");
+        assert_eq!(read_jline(&mut reader).await.unwrap().unwrap(), "");
+        assert!(read_jline(&mut reader).await.is_err());
+        assert_eq!(read_jline(&mut reader).await.unwrap().unwrap(), "last");
+        assert!(read_jline(&mut reader).await.unwrap().is_none());
+    }
+}
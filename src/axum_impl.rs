@@ -0,0 +1,82 @@
+use crate::JavaString;
+use axum::body::Body;
+use axum::response::{IntoResponse, Response};
+
+impl IntoResponse for JavaString {
+    /// Mirrors the `String` implementation, so the body is sent as
+    /// `text/plain; charset=utf-8`.
+    fn into_response(self) -> Response {
+        String::from(self.as_str()).into_response()
+    }
+}
+
+impl From<JavaString> for Body {
+    fn from(string: JavaString) -> Self {
+        Body::from(String::from(string.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use axum::http::{header, Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    async fn get_response(app: Router, uri: &str) -> Response {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn handler_returns_java_string() {
+        let app = Router::new()
+            .route("/short", get(|| async { JavaString::from("hello") }))
+            .route(
+                "/long",
+                get(|| async { JavaString::from("a response that lives on the heap") }),
+            );
+
+        for (uri, expected) in &[
+            ("/short", "hello"),
+            ("/long", "a response that lives on the heap"),
+        ] {
+            let response = get_response(app.clone(), uri).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "text/plain; charset=utf-8"
+            );
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, expected.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_returns_status_and_java_string() {
+        let app = Router::new().route(
+            "/",
+            get(|| async { (StatusCode::NOT_FOUND, JavaString::from("missing")) }),
+        );
+
+        let response = get_response(app, "/").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "missing");
+    }
+
+    #[tokio::test]
+    async fn body_from_java_string() {
+        let body = Body::from(JavaString::from("body"));
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(bytes, "body");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::raw_string::RawJavaString;
+use core::fmt;
+use core::ops::Deref;
+
+/// The bytes of a `JavaString`, still in the string's own buffer.
+///
+/// This struct is created by the [`into_java_bytes`] method on `JavaString`.
+/// Unlike [`into_bytes`], which has to copy into a `Vec`, this takes over the
+/// string's storage as-is: heap buffers keep their address, and short strings
+/// stay interned. The buffer is freed with the layout it was allocated with.
+///
+/// [`into_java_bytes`]: struct.JavaString.html#method.into_java_bytes
+/// [`into_bytes`]: struct.JavaString.html#method.into_bytes
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct JavaBytes {
+    data: RawJavaString,
+}
+
+impl JavaBytes {
+    pub(crate) fn new(data: RawJavaString) -> Self {
+        Self { data }
+    }
+
+    /// Returns the bytes as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.get_bytes()
+    }
+
+    /// Copies the bytes into a new `Vec`.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data.into_bytes()
+    }
+}
+
+impl Deref for JavaBytes {
+    type Target = [u8];
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for JavaBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl core::borrow::Borrow<[u8]> for JavaBytes {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for JavaBytes {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Debug::fmt(self.as_slice(), formatter)
+    }
+}
+
+impl From<JavaBytes> for Vec<u8> {
+    fn from(bytes: JavaBytes) -> Self {
+        bytes.into_vec()
+    }
+}
+
+impl PartialEq<[u8]> for JavaBytes {
+    fn eq(&self, rhs: &[u8]) -> bool {
+        self.as_slice() == rhs
+    }
+}
+
+impl PartialEq<&[u8]> for JavaBytes {
+    fn eq(&self, rhs: &&[u8]) -> bool {
+        self.as_slice() == *rhs
+    }
+}
+
+impl PartialEq<Vec<u8>> for JavaBytes {
+    fn eq(&self, rhs: &Vec<u8>) -> bool {
+        self.as_slice() == &rhs[..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::{count_allocations, live_bytes};
+    use crate::JavaString;
+
+    #[test]
+    fn heap_buffer_is_handed_over() {
+        // Run under miri to check that the buffer changes owners exactly once,
+        // and is freed with the layout it was allocated with.
+        let text = "a string that lives on the heap";
+        let string = JavaString::from(text);
+        let ptr = string.as_ptr();
+        let before = live_bytes();
+
+        let (bytes, allocations) = count_allocations(|| string.into_java_bytes());
+        assert_eq!(allocations, 0);
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(bytes, text.as_bytes());
+        assert_eq!(live_bytes(), before);
+
+        drop(bytes);
+        assert_eq!(live_bytes(), before - text.len() as isize);
+    }
+
+    #[test]
+    fn interned_bytes() {
+        let (bytes, allocations) =
+            count_allocations(|| JavaString::from("short").into_java_bytes());
+        assert_eq!(allocations, 0);
+        assert_eq!(bytes, &b"short"[..]);
+        assert_eq!(format!("{:?}", bytes), format!("{:?}", b"short"));
+
+        let vec = Vec::from(bytes.clone());
+        assert_eq!(vec, b"short");
+        assert_eq!(bytes, vec);
+    }
+}
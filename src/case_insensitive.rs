@@ -0,0 +1,131 @@
+use crate::JavaString;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+/// Wrapper that compares, orders and hashes a `JavaString` ignoring ASCII
+/// case, for keys like HTTP header names.
+///
+/// Only ASCII letters are folded; other characters must match exactly.
+///
+/// # Examples
+///
+/// ```
+/// # use jstring::{CaseInsensitive, JavaString};
+/// use std::collections::HashMap;
+///
+/// let mut headers = HashMap::new();
+/// headers.insert(CaseInsensitive::from("Content-Type"), "text/plain");
+///
+/// assert_eq!(headers[&CaseInsensitive::from("content-type")], "text/plain");
+/// ```
+#[derive(Clone, Debug)]
+pub struct CaseInsensitive(pub JavaString);
+
+impl CaseInsensitive {
+    /// Returns the wrapped string, with its original case.
+    pub fn into_inner(self) -> JavaString {
+        self.0
+    }
+
+    fn folded(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.bytes().map(|b| b.to_ascii_lowercase())
+    }
+}
+
+impl From<JavaString> for CaseInsensitive {
+    fn from(string: JavaString) -> Self {
+        Self(string)
+    }
+}
+
+impl From<&str> for CaseInsensitive {
+    fn from(string: &str) -> Self {
+        Self(JavaString::from(string))
+    }
+}
+
+impl PartialEq for CaseInsensitive {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for CaseInsensitive {}
+
+impl PartialOrd for CaseInsensitive {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CaseInsensitive {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded().cmp(other.folded())
+    }
+}
+
+impl Hash for CaseInsensitive {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        for b in self.folded() {
+            hasher.write_u8(b);
+        }
+        // Same terminator as `str`, so that tuples of keys stay prefix-free.
+        hasher.write_u8(0xff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::hash_of;
+    use std::collections::{BTreeSet, HashMap};
+
+    #[test]
+    fn hash_map_lookup() {
+        let mut headers = HashMap::new();
+        headers.insert(CaseInsensitive::from("Content-Type"), 1);
+        headers.insert(CaseInsensitive::from("X-A-Very-Long-Header-Name"), 2);
+
+        assert_eq!(headers[&CaseInsensitive::from("content-type")], 1);
+        assert_eq!(headers[&CaseInsensitive::from("CONTENT-TYPE")], 1);
+        assert_eq!(
+            headers[&CaseInsensitive::from("x-a-very-long-header-name")],
+            2
+        );
+        assert!(!headers.contains_key(&CaseInsensitive::from("Content-Length")));
+
+        headers.insert(CaseInsensitive::from("CONTENT-type"), 3);
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[test]
+    fn eq_hash_and_ord_agree() {
+        let pairs = [
+            ("Content-Type", "content-TYPE", true),
+            ("ÀB", "Àb", true),
+            ("ÀB", "àb", false),
+            ("abc", "abd", false),
+            ("", "", true),
+        ];
+
+        for &(a, b, equal) in &pairs {
+            let (a, b) = (CaseInsensitive::from(a), CaseInsensitive::from(b));
+            assert_eq!(a == b, equal);
+            assert_eq!(a.cmp(&b) == Ordering::Equal, equal);
+            if equal {
+                assert_eq!(hash_of(&a), hash_of(&b));
+            }
+        }
+
+        let sorted: BTreeSet<_> = ["b", "A", "a", "C", "B"]
+            .iter()
+            .map(|&s| CaseInsensitive::from(s))
+            .collect();
+        let sorted: Vec<_> = sorted
+            .into_iter()
+            .map(|key| key.into_inner().to_ascii_lowercase())
+            .collect();
+        assert_eq!(sorted, ["a", "b", "c"]);
+    }
+}
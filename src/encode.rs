@@ -0,0 +1,446 @@
+//! Base64 and hex conversions between binary data and `JavaString`.
+//!
+//! Encoding writes straight into a buffer of exactly the right size, so short
+//! outputs (digests, small tokens) end up interned. Decoding rejects anything
+//! that isn't part of the alphabet, including whitespace.
+
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use core::fmt;
+
+const STANDARD_SYMBOLS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_SYMBOLS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEX_SYMBOLS: &[u8; 16] = b"0123456789abcdef";
+
+/// A base64 alphabet, along with whether or not it uses `=` padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Base64 {
+    symbols: &'static [u8; 64],
+    pad: bool,
+}
+
+impl Base64 {
+    /// The standard alphabet from RFC 4648, with padding.
+    pub const STANDARD: Self = Self {
+        symbols: STANDARD_SYMBOLS,
+        pad: true,
+    };
+
+    /// The standard alphabet from RFC 4648, without padding.
+    pub const STANDARD_NO_PAD: Self = Self {
+        symbols: STANDARD_SYMBOLS,
+        pad: false,
+    };
+
+    /// The URL and filename safe alphabet from RFC 4648, with padding.
+    pub const URL_SAFE: Self = Self {
+        symbols: URL_SAFE_SYMBOLS,
+        pad: true,
+    };
+
+    /// The URL and filename safe alphabet from RFC 4648, without padding.
+    pub const URL_SAFE_NO_PAD: Self = Self {
+        symbols: URL_SAFE_SYMBOLS,
+        pad: false,
+    };
+
+    /// Returns the length of the encoding of `len` bytes.
+    fn encoded_len(self, len: usize) -> usize {
+        if self.pad {
+            len.div_ceil(3) * 4
+        } else {
+            len / 3 * 4 + [0, 2, 3][len % 3]
+        }
+    }
+
+    fn decode_symbol(self, symbol: u8) -> Option<u8> {
+        match symbol {
+            b'A'..=b'Z' => Some(symbol - b'A'),
+            b'a'..=b'z' => Some(symbol - b'a' + 26),
+            b'0'..=b'9' => Some(symbol - b'0' + 52),
+            _ if symbol == self.symbols[62] => Some(62),
+            _ if symbol == self.symbols[63] => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// The error returned when decoding base64 or hex fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The byte at `position` isn't part of the alphabet.
+    InvalidByte { position: usize, byte: u8 },
+    /// The input has a length that no valid encoding could have.
+    InvalidLength { len: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            DecodeError::InvalidByte { position, byte } => {
+                write!(
+                    formatter,
+                    "invalid byte {:#04x} at position {}",
+                    byte, position
+                )
+            }
+            DecodeError::InvalidLength { len } => write!(formatter, "invalid length {}", len),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl JavaString {
+    /// Encodes `bytes` as base64, using the standard alphabet with padding.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from_base64(b"foobar");
+    ///
+    /// assert_eq!(s, "Zm9vYmFy");
+    /// ```
+    pub fn from_base64(bytes: &[u8]) -> JavaString {
+        Self::from_base64_with(bytes, Base64::STANDARD)
+    }
+
+    /// Encodes `bytes` as base64, using the given alphabet.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// use jstring::encode::Base64;
+    ///
+    /// let s = JavaString::from_base64_with(&[0xfb, 0xff], Base64::URL_SAFE_NO_PAD);
+    ///
+    /// assert_eq!(s, "-_8");
+    /// ```
+    pub fn from_base64_with(bytes: &[u8], alphabet: Base64) -> JavaString {
+        let mut data = RawJavaString::zeroed(alphabet.encoded_len(bytes.len()));
+        let symbol = |value: u32| alphabet.symbols[(value & 0x3f) as usize];
+        let (body, tail) = data.get_bytes_mut().split_at_mut(bytes.len() / 3 * 4);
+
+        let mut chunks = bytes.chunks_exact(3);
+        for (chunk, out) in (&mut chunks).zip(body.chunks_exact_mut(4)) {
+            let value = u32::from_be_bytes([0, chunk[0], chunk[1], chunk[2]]);
+            out.copy_from_slice(&[
+                symbol(value >> 18),
+                symbol(value >> 12),
+                symbol(value >> 6),
+                symbol(value),
+            ]);
+        }
+
+        // Whatever's left after the last symbol is padding, if the alphabet
+        // uses it.
+        match *chunks.remainder() {
+            [a] => {
+                let value = u32::from(a) << 16;
+                tail[0..2].copy_from_slice(&[symbol(value >> 18), symbol(value >> 12)]);
+                tail[2..].fill(b'=');
+            }
+            [a, b] => {
+                let value = (u32::from(a) << 16) | (u32::from(b) << 8);
+                tail[0..3].copy_from_slice(&[
+                    symbol(value >> 18),
+                    symbol(value >> 12),
+                    symbol(value >> 6),
+                ]);
+                tail[3..].fill(b'=');
+            }
+            _ => {}
+        }
+
+        Self { data }
+    }
+
+    /// Decodes this string as base64, using the standard alphabet with padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the string contains anything outside the alphabet
+    /// (whitespace included), or if its length isn't a valid encoded length.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("Zm9vYg==");
+    ///
+    /// assert_eq!(s.decode_base64().unwrap(), b"foob");
+    /// ```
+    pub fn decode_base64(&self) -> Result<Vec<u8>, DecodeError> {
+        self.decode_base64_with(Base64::STANDARD)
+    }
+
+    /// Decodes this string as base64, using the given alphabet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the string contains anything outside the alphabet
+    /// (whitespace included), or if its length isn't a valid encoded length.
+    /// Padding is required if the alphabet uses it, and rejected otherwise.
+    pub fn decode_base64_with(&self, alphabet: Base64) -> Result<Vec<u8>, DecodeError> {
+        let mut data = self.as_bytes();
+        if alphabet.pad {
+            if data.len() % 4 != 0 {
+                return Err(DecodeError::InvalidLength { len: data.len() });
+            }
+
+            let padding = data
+                .iter()
+                .rev()
+                .take(2)
+                .take_while(|&&b| b == b'=')
+                .count();
+            data = &data[0..data.len() - padding];
+        }
+
+        if data.len() % 4 == 1 {
+            return Err(DecodeError::InvalidLength {
+                len: self.as_bytes().len(),
+            });
+        }
+
+        let mut out = Vec::with_capacity(data.len() * 3 / 4);
+        let (mut acc, mut bits) = (0u32, 0);
+        for (position, &byte) in data.iter().enumerate() {
+            let value = alphabet
+                .decode_symbol(byte)
+                .ok_or(DecodeError::InvalidByte { position, byte })?;
+
+            acc = (acc << 6) | u32::from(value);
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+                acc &= (1 << bits) - 1;
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Encodes `bytes` as lowercase hex.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from_hex(&[0xde, 0xad, 0xbe, 0xef]);
+    ///
+    /// assert_eq!(s, "deadbeef");
+    /// ```
+    pub fn from_hex(bytes: &[u8]) -> JavaString {
+        let mut data = RawJavaString::zeroed(bytes.len() * 2);
+        for (byte, out) in bytes.iter().zip(data.get_bytes_mut().chunks_exact_mut(2)) {
+            out[0] = HEX_SYMBOLS[(byte >> 4) as usize];
+            out[1] = HEX_SYMBOLS[(byte & 0xf) as usize];
+        }
+        Self { data }
+    }
+
+    /// Decodes this string as hex. Both uppercase and lowercase digits are
+    /// accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the string has an odd length, or contains anything
+    /// that isn't a hex digit.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("DEADbeef");
+    ///
+    /// assert_eq!(s.decode_hex().unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+    /// assert!(JavaString::from("abc").decode_hex().is_err());
+    /// ```
+    pub fn decode_hex(&self) -> Result<Vec<u8>, DecodeError> {
+        let data = self.as_bytes();
+        if data.len() % 2 != 0 {
+            return Err(DecodeError::InvalidLength { len: data.len() });
+        }
+
+        let digit = |position: usize| {
+            let byte = data[position];
+            match byte {
+                b'0'..=b'9' => Ok(byte - b'0'),
+                b'a'..=b'f' => Ok(byte - b'a' + 10),
+                b'A'..=b'F' => Ok(byte - b'A' + 10),
+                _ => Err(DecodeError::InvalidByte { position, byte }),
+            }
+        };
+
+        (0..data.len())
+            .step_by(2)
+            .map(|position| Ok((digit(position)? << 4) | digit(position + 1)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const RFC_4648_VECTORS: &[(&str, &str)] = &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn base64_rfc_vectors() {
+        for &(plain, encoded) in RFC_4648_VECTORS {
+            let string = JavaString::from_base64(plain.as_bytes());
+            assert_eq!(string, encoded);
+            assert!(string.data.is_interned());
+            assert_eq!(string.decode_base64().unwrap(), plain.as_bytes());
+
+            let unpadded = encoded.trim_end_matches('=');
+            let string = JavaString::from_base64_with(plain.as_bytes(), Base64::STANDARD_NO_PAD);
+            assert_eq!(string, unpadded);
+            assert_eq!(
+                string.decode_base64_with(Base64::STANDARD_NO_PAD).unwrap(),
+                plain.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for alphabet in &[
+            Base64::STANDARD,
+            Base64::STANDARD_NO_PAD,
+            Base64::URL_SAFE,
+            Base64::URL_SAFE_NO_PAD,
+        ] {
+            // Every remainder and padding case shows up well before 256 bytes.
+            let max_len = if cfg!(miri) { 32 } else { bytes.len() };
+            for len in 0..max_len {
+                let string = JavaString::from_base64_with(&bytes[0..len], *alphabet);
+                assert_eq!(string.len(), alphabet.encoded_len(len));
+                assert_eq!(
+                    string.decode_base64_with(*alphabet).unwrap(),
+                    &bytes[0..len]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn base64_url_safe() {
+        let bytes = [0xfb, 0xef, 0xff];
+        assert_eq!(JavaString::from_base64(&bytes), "++//");
+        assert_eq!(
+            JavaString::from_base64_with(&bytes, Base64::URL_SAFE),
+            "--__"
+        );
+
+        let string = JavaString::from("--__");
+        assert_eq!(string.decode_base64_with(Base64::URL_SAFE).unwrap(), bytes);
+        assert_eq!(
+            string.decode_base64(),
+            Err(DecodeError::InvalidByte {
+                position: 0,
+                byte: b'-'
+            })
+        );
+    }
+
+    #[test]
+    fn base64_rejects_bad_input() {
+        assert_eq!(
+            JavaString::from("Zm9v\nYmF").decode_base64_with(Base64::STANDARD_NO_PAD),
+            Err(DecodeError::InvalidByte {
+                position: 4,
+                byte: b'\n'
+            })
+        );
+        assert_eq!(
+            JavaString::from("Zm9vYg").decode_base64(),
+            Err(DecodeError::InvalidLength { len: 6 })
+        );
+        assert_eq!(
+            JavaString::from("Zm9vY").decode_base64_with(Base64::STANDARD_NO_PAD),
+            Err(DecodeError::InvalidLength { len: 5 })
+        );
+        assert_eq!(
+            JavaString::from("Zm8=").decode_base64_with(Base64::STANDARD_NO_PAD),
+            Err(DecodeError::InvalidByte {
+                position: 3,
+                byte: b'='
+            })
+        );
+    }
+
+    #[test]
+    fn encode_allocations() {
+        use crate::tests::count_allocations;
+
+        // Short encodings are interned, so nothing touches the heap.
+        let (string, allocations) = count_allocations(|| JavaString::from_hex(&[0xab; 4]));
+        assert_eq!(allocations, 0);
+        assert_eq!(string, "abababab");
+        let (string, allocations) = count_allocations(|| JavaString::from_base64(&[0xab; 6]));
+        assert_eq!(allocations, 0);
+        assert_eq!(string, "q6urq6ur");
+
+        // Longer ones are written straight into the string's own buffer.
+        let digest = [0xab; 32];
+        let (string, allocations) = count_allocations(|| JavaString::from_hex(&digest));
+        assert_eq!(allocations, 1);
+        assert_eq!(string, "ab".repeat(32).as_str());
+        let (string, allocations) = count_allocations(|| JavaString::from_base64(&digest));
+        assert_eq!(allocations, 1);
+        assert_eq!(string.decode_base64().unwrap(), digest);
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(JavaString::from_hex(b"foobar"), "666f6f626172");
+        assert_eq!(
+            JavaString::from("666F6F626172").decode_hex().unwrap(),
+            b"foobar"
+        );
+
+        let digest = [0xab; 32];
+        let string = JavaString::from_hex(&digest);
+        assert!(!string.data.is_interned());
+        assert_eq!(string.decode_hex().unwrap(), digest);
+
+        assert_eq!(
+            JavaString::from("abc").decode_hex(),
+            Err(DecodeError::InvalidLength { len: 3 })
+        );
+        assert_eq!(
+            JavaString::from("0g").decode_hex(),
+            Err(DecodeError::InvalidByte {
+                position: 1,
+                byte: b'g'
+            })
+        );
+    }
+}
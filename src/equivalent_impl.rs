@@ -0,0 +1,84 @@
+use crate::JavaString;
+use equivalent::Equivalent;
+
+// These let maps keyed by `JavaString` (like `hashbrown::HashMap` and
+// `indexmap::IndexMap`) be probed with `String`s, and maps keyed by `String` be
+// probed with a `JavaString`. Probing with a borrowed `str` is covered by the
+// blanket impl over `Borrow<str>`. All of them hash the same way as `str`, so
+// equivalent keys always have equal hashes.
+
+impl Equivalent<JavaString> for String {
+    fn equivalent(&self, key: &JavaString) -> bool {
+        self.as_str() == key.as_str()
+    }
+}
+
+impl Equivalent<String> for JavaString {
+    fn equivalent(&self, key: &String) -> bool {
+        self.as_str() == key.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const KEYS: &[&str] = &["short", "a key that lives on the heap"];
+
+    #[test]
+    fn hashbrown_lookup() {
+        let mut map = hashbrown::HashMap::new();
+        for (idx, key) in KEYS.iter().enumerate() {
+            map.insert(JavaString::from(*key), idx);
+        }
+
+        for (idx, key) in KEYS.iter().enumerate() {
+            assert_eq!(map.get(*key), Some(&idx));
+            assert_eq!(map.get(&String::from(*key)), Some(&idx));
+        }
+        assert_eq!(map.get("missing"), None);
+    }
+
+    #[test]
+    fn hashbrown_insert_without_key() {
+        let mut map: hashbrown::HashMap<JavaString, usize> = hashbrown::HashMap::new();
+
+        *map.entry_ref("short").or_insert(0) += 1;
+        *map.entry_ref("short").or_insert(0) += 1;
+        map.raw_entry_mut()
+            .from_key("a key that lives on the heap")
+            .or_insert_with(|| ("a key that lives on the heap".into(), 5));
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["short"], 2);
+        assert_eq!(map["a key that lives on the heap"], 5);
+    }
+
+    #[test]
+    fn indexmap_lookup() {
+        let mut map = indexmap::IndexMap::new();
+        for (idx, key) in KEYS.iter().enumerate() {
+            map.insert(JavaString::from(*key), idx);
+        }
+
+        for (idx, key) in KEYS.iter().enumerate() {
+            assert_eq!(map.get(*key), Some(&idx));
+            assert_eq!(map.get(&String::from(*key)), Some(&idx));
+            assert_eq!(map.get_index_of(*key), Some(idx));
+        }
+        assert_eq!(map.get("missing"), None);
+    }
+
+    #[test]
+    fn string_keys_probed_by_java_string() {
+        let mut map = indexmap::IndexMap::new();
+        for (idx, key) in KEYS.iter().enumerate() {
+            map.insert(String::from(*key), idx);
+        }
+
+        for (idx, key) in KEYS.iter().enumerate() {
+            assert_eq!(map.get(&JavaString::from(*key)), Some(&idx));
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use core::fmt;
+
+/// The error returned by the `checked_*` methods of `JavaString` when they're
+/// given an index that can't be used. The string is left untouched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundsError {
+    /// The byte index `index` is past the end of a string of length `len`.
+    OutOfBounds { index: usize, len: usize },
+    /// The byte index `index` is inside a character.
+    NotCharBoundary { index: usize },
+    /// The range starts at byte index `start`, after it ends at `end`.
+    InvalidRange { start: usize, end: usize },
+}
+
+impl fmt::Display for BoundsError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            BoundsError::OutOfBounds { index, len } => write!(
+                formatter,
+                "byte index {} is out of bounds of a string of length {}",
+                index, len
+            ),
+            BoundsError::NotCharBoundary { index } => {
+                write!(formatter, "byte index {} is not a char boundary", index)
+            }
+            BoundsError::InvalidRange { start, end } => write!(
+                formatter,
+                "range starts at byte index {} but ends at {}",
+                start, end
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoundsError {}
@@ -0,0 +1,64 @@
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// A guard giving mutable access to a copy of a `JavaString`'s bytes.
+///
+/// This struct is created by the [`as_mut_vec`] method on `JavaString`. When
+/// it's dropped, the bytes are checked for valid UTF-8 and written back into
+/// the string, which is interned if it's now short enough.
+///
+/// # Panics
+///
+/// Dropping the guard panics if the bytes aren't valid UTF-8. The string is
+/// left as it was before the guard was created.
+///
+/// [`as_mut_vec`]: struct.JavaString.html#method.as_mut_vec
+pub struct MutVecGuard<'a> {
+    string: &'a mut JavaString,
+    bytes: Vec<u8>,
+}
+
+impl<'a> MutVecGuard<'a> {
+    pub(crate) fn new(string: &'a mut JavaString) -> Self {
+        let bytes = string.as_bytes().to_vec();
+        Self { string, bytes }
+    }
+}
+
+impl Deref for MutVecGuard<'_> {
+    type Target = Vec<u8>;
+    fn deref(&self) -> &Vec<u8> {
+        &self.bytes
+    }
+}
+
+impl DerefMut for MutVecGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+}
+
+impl fmt::Debug for MutVecGuard<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        formatter
+            .debug_struct("MutVecGuard")
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+impl Drop for MutVecGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = core::str::from_utf8(&self.bytes) {
+            // Don't turn an unwind that's already happening into an abort.
+            if !std::thread::panicking() {
+                panic!("MutVecGuard holds invalid UTF-8: {}", e);
+            }
+            return;
+        }
+
+        self.string.data = RawJavaString::from_bytes(&self.bytes[..]);
+    }
+}
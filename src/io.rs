@@ -0,0 +1,219 @@
+//! Reading lines from a `BufRead` as `JavaString`s.
+//!
+//! Each line is copied exactly once, straight out of the reader's buffer when
+//! it fits there, so short lines are interned without touching the heap.
+
+use crate::JavaString;
+use std::io::{self, BufRead};
+
+/// An iterator over the lines of a `BufRead`, created by [`jlines`].
+///
+/// [`jlines`]: fn.jlines.html
+#[derive(Debug)]
+pub struct JLines<R> {
+    reader: R,
+    scratch: Vec<u8>,
+}
+
+/// Returns an iterator over the lines of `reader`, without their `\n` or
+/// `\r\n` endings, like `BufRead::lines`.
+///
+/// A line that isn't valid UTF-8 is returned as an error of kind
+/// `InvalidData`. Unlike `BufRead::lines`, the line is consumed anyway, so
+/// iteration can carry on with the next line.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use jstring::io::jlines;
+///
+/// let lines: Vec<_> = jlines(&b"foo\r\nbar\nbaz"[..])
+///     .map(Result::unwrap)
+///     .collect();
+///
+/// assert_eq!(lines, ["foo", "bar", "baz"]);
+/// ```
+pub fn jlines<R: BufRead>(reader: R) -> JLines<R> {
+    JLines {
+        reader,
+        scratch: Vec::new(),
+    }
+}
+
+impl<R: BufRead> Iterator for JLines<R> {
+    type Item = io::Result<JavaString>;
+
+    fn next(&mut self) -> Option<io::Result<JavaString>> {
+        match read_line_with(&mut self.reader, &mut self.scratch) {
+            Ok(Some((line, _))) => Some(line),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Reads the next line from `reader` into `string`, without its `\n` or
+/// `\r\n` ending, replacing whatever `string` held before. Returns how many
+/// bytes were consumed from `reader`, which is 0 once it has reached its end.
+///
+/// # Errors
+///
+/// Returns an error of kind `InvalidData` if the line isn't valid UTF-8,
+/// leaving `string` untouched. The line is consumed either way, so the next
+/// call moves on to the next line.
+pub fn read_jline<R: BufRead>(reader: &mut R, string: &mut JavaString) -> io::Result<usize> {
+    match read_line_with(reader, &mut Vec::new())? {
+        Some((line, consumed)) => {
+            *string = line?;
+            Ok(consumed)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Reads the next line from `reader`, using `scratch` only for lines that
+/// span more than one fill of the reader's buffer. The outer error is from
+/// `reader`, and the inner one is for invalid UTF-8.
+fn read_line_with<R: BufRead>(
+    reader: &mut R,
+    scratch: &mut Vec<u8>,
+) -> io::Result<Option<(io::Result<JavaString>, usize)>> {
+    scratch.clear();
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        if available.is_empty() {
+            break;
+        }
+
+        match available.iter().position(|&b| b == b'\n') {
+            Some(newline) if scratch.is_empty() => {
+                let line = build_line(&available[..=newline]);
+                reader.consume(newline + 1);
+                return Ok(Some((line, newline + 1)));
+            }
+            Some(newline) => {
+                scratch.extend_from_slice(&available[..=newline]);
+                reader.consume(newline + 1);
+                break;
+            }
+            None => {
+                let len = available.len();
+                scratch.extend_from_slice(available);
+                reader.consume(len);
+            }
+        }
+    }
+
+    if scratch.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some((build_line(scratch), scratch.len())))
+}
+
+fn build_line(mut line: &[u8]) -> io::Result<JavaString> {
+    if let Some(rest) = line.strip_suffix(b"\n") {
+        line = rest.strip_suffix(b"\r").unwrap_or(rest);
+    }
+
+    core::str::from_utf8(line)
+        .map(JavaString::from)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::count_allocations;
+    use std::io::BufReader;
+
+    fn collect(text: &[u8], capacity: usize) -> Vec<Result<JavaString, io::ErrorKind>> {
+        jlines(BufReader::with_capacity(capacity, text))
+            .map(|line| line.map_err(|e| e.kind()))
+            .collect()
+    }
+
+    #[test]
+    fn line_endings() {
+        let text = "first\r\nsecond 💖\n\r\n\nthis line is long enough for the heap\r\nlast";
+
+        // A tiny buffer splits lines, and even line endings, across fills.
+        for &capacity in &[1, 2, 3, 4096] {
+            assert_eq!(
+                collect(text.as_bytes(), capacity),
+                [
+                    Ok("first".into()),
+                    Ok("second 💖".into()),
+                    Ok("".into()),
+                    Ok("".into()),
+                    Ok("this line is long enough for the heap".into()),
+                    Ok("last".into()),
+                ]
+            );
+            assert_eq!(collect(b"only\n", capacity), [Ok("only".into())]);
+        }
+
+        assert_eq!(collect(b"", 16), []);
+    }
+
+    #[test]
+    fn invalid_lines_dont_stop_iteration() {
+        for &capacity in &[2, 4096] {
+            assert_eq!(
+                collect(b"good\nbad \xff\r\nalso good", capacity),
+                [
+                    Ok("good".into()),
+                    Err(io::ErrorKind::InvalidData),
+                    Ok("also good".into()),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn read_into_string() {
+        let mut reader = &b"first\r\nbad \xff\nlast"[..];
+        let mut string = JavaString::new();
+
+        assert_eq!(read_jline(&mut reader, &mut string).unwrap(), 7);
+        assert_eq!(string, "first");
+
+        let err = read_jline(&mut reader, &mut string).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(string, "first");
+
+        assert_eq!(read_jline(&mut reader, &mut string).unwrap(), 4);
+        assert_eq!(string, "last");
+
+        assert_eq!(read_jline(&mut reader, &mut string).unwrap(), 0);
+        assert_eq!(string, "last");
+    }
+
+    #[test]
+    fn allocations() {
+        let text = "short line\n".repeat(100);
+
+        let ((), jlines_allocations) = count_allocations(|| {
+            for line in jlines(text.as_bytes()) {
+                assert_eq!(line.unwrap(), "short line");
+            }
+        });
+        let ((), std_allocations) = count_allocations(|| {
+            for line in text.as_bytes().lines() {
+                assert_eq!(line.unwrap(), "short line");
+            }
+        });
+
+        assert_eq!(jlines_allocations, 0);
+        assert!(std_allocations >= 100);
+    }
+}
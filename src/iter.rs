@@ -0,0 +1,236 @@
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use core::iter::FusedIterator;
+
+/// An owning iterator over the `char`s of a `JavaString`.
+///
+/// This struct is created by the [`into_chars`] method on `JavaString`. The
+/// string's buffer is freed as soon as the last character has been read, or
+/// when the iterator is dropped, whichever comes first.
+///
+/// [`into_chars`]: struct.JavaString.html#method.into_chars
+#[derive(Clone)]
+pub struct IntoChars {
+    data: RawJavaString,
+    front: usize,
+    back: usize,
+}
+
+impl IntoChars {
+    pub(crate) fn new(data: RawJavaString) -> Self {
+        let back = data.len();
+        Self {
+            data,
+            front: 0,
+            back,
+        }
+    }
+
+    /// Returns the characters that haven't been read yet, as a string slice.
+    pub fn as_str(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.data.get_bytes()[self.front..self.back]) }
+    }
+
+    /// Frees the buffer once everything has been read.
+    fn release_if_done(&mut self) {
+        if self.front == self.back {
+            self.data = RawJavaString::new();
+            self.front = 0;
+            self.back = 0;
+        }
+    }
+}
+
+impl Iterator for IntoChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.front += ch.len_utf8();
+        self.release_if_done();
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl DoubleEndedIterator for IntoChars {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.back -= ch.len_utf8();
+        self.release_if_done();
+        Some(ch)
+    }
+}
+
+impl FusedIterator for IntoChars {}
+
+impl core::fmt::Debug for IntoChars {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        formatter
+            .debug_tuple("IntoChars")
+            .field(&self.as_str())
+            .finish()
+    }
+}
+
+/// A draining iterator for `JavaString`.
+///
+/// This struct is created by the [`drain`] method on `JavaString`. The drained
+/// range is only removed from the string once the iterator is dropped, at
+/// which point the bytes on either side of it are copied into a new buffer.
+///
+/// [`drain`]: struct.JavaString.html#method.drain
+pub struct Drain<'a> {
+    string: &'a mut JavaString,
+    start: usize,
+    end: usize,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Drain<'a> {
+    pub(crate) fn new(string: &'a mut JavaString, start: usize, end: usize) -> Self {
+        Self {
+            string,
+            start,
+            end,
+            front: start,
+            back: end,
+        }
+    }
+
+    /// Returns the characters that haven't been read yet, as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.string.as_str()[self.front..self.back]
+    }
+}
+
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next()?;
+        self.front += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len.div_ceil(4), Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Drain<'_> {
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.back -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl FusedIterator for Drain<'_> {}
+
+impl Drop for Drain<'_> {
+    fn drop(&mut self) {
+        let bytes = self.string.as_bytes();
+        let bytes_array: &[&[u8]] = &[&bytes[..self.start], &bytes[self.end..]];
+        self.string.data = RawJavaString::from_bytes_array(bytes_array);
+    }
+}
+
+impl core::fmt::Debug for Drain<'_> {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        formatter
+            .debug_tuple("Drain")
+            .field(&self.as_str())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::live_bytes;
+    use crate::JavaString;
+
+    #[test]
+    fn consume_fully() {
+        let string = JavaString::from("héllo 💖, this string lives on the heap");
+        let chars: Vec<char> = string.clone().into_chars().collect();
+        assert_eq!(chars, string.chars().collect::<Vec<_>>());
+
+        let reversed: String = string.clone().into_chars().rev().collect();
+        assert_eq!(reversed, string.chars().rev().collect::<String>());
+
+        let mut chars = JavaString::from("a💖b").into_chars();
+        assert_eq!(chars.next(), Some('a'));
+        assert_eq!(chars.next_back(), Some('b'));
+        assert_eq!(chars.as_str(), "💖");
+        assert_eq!(chars.next_back(), Some('💖'));
+        assert_eq!(chars.next(), None);
+        assert_eq!(chars.next_back(), None);
+    }
+
+    #[test]
+    fn buffer_freed() {
+        let before = live_bytes();
+        let mut chars = JavaString::from("a string that lives on the heap").into_chars();
+        assert!(live_bytes() > before);
+
+        assert_eq!(chars.by_ref().count(), 31);
+        assert_eq!(live_bytes(), before);
+    }
+
+    #[test]
+    fn partial_consumption_frees_remainder() {
+        let before = live_bytes();
+        let mut chars = JavaString::from("a string that lives on the heap").into_chars();
+        assert_eq!(chars.next(), Some('a'));
+        assert_eq!(chars.next_back(), Some('p'));
+        drop(chars);
+
+        assert_eq!(live_bytes(), before);
+    }
+
+    #[test]
+    fn drain_partially_consumed() {
+        for text in &["a💖bc", "a💖b, and enough to put this string on the heap"] {
+            let mut string = JavaString::from(*text);
+            let mut drain = string.drain(1..6);
+            assert_eq!(drain.next(), Some('💖'));
+            assert_eq!(drain.as_str(), "b");
+            drop(drain);
+
+            let mut expected = String::from(*text);
+            expected.replace_range(1..6, "");
+            assert_eq!(string, expected.as_str());
+        }
+    }
+
+    #[test]
+    fn drain_from_both_ends() {
+        let mut string = JavaString::from("héllo 💖, this string lives on the heap");
+        let drained: String = string.drain(..11).rev().collect();
+        assert_eq!(drained, "💖 olléh");
+        assert_eq!(string, ", this string lives on the heap");
+
+        let drained: Vec<char> = string.drain(..).collect();
+        assert_eq!(drained.len(), 31);
+        assert!(string.is_empty());
+        assert!(string.stats().is_interned);
+
+        let mut string = JavaString::from("untouched");
+        string.drain(3..3);
+        assert_eq!(string, "untouched");
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 2 is not a char boundary")]
+    fn drain_panics_off_boundary() {
+        JavaString::from("a💖b").drain(2..);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use core::convert::TryFrom;
+use core::fmt;
+use serde_json::Value;
+
+/// The error returned when converting a `serde_json::Value` that isn't a string
+/// into a `JavaString`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotAString {
+    found: &'static str,
+}
+
+impl NotAString {
+    /// Returns the type of the value that was converted, e.g. `"number"`.
+    pub fn found(&self) -> &'static str {
+        self.found
+    }
+}
+
+impl fmt::Display for NotAString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "expected a string, found {}", self.found)
+    }
+}
+
+impl std::error::Error for NotAString {}
+
+impl From<JavaString> for Value {
+    fn from(string: JavaString) -> Self {
+        Value::String(string.as_str().to_owned())
+    }
+}
+
+impl<'a> From<&'a JavaString> for Value {
+    fn from(string: &'a JavaString) -> Self {
+        Value::String(string.as_str().to_owned())
+    }
+}
+
+impl TryFrom<Value> for JavaString {
+    type Error = NotAString;
+
+    /// Succeeds only for `Value::String`. Strings too long to be interned are
+    /// copied into a buffer of exactly their length.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let found = match value {
+            Value::String(string) => {
+                return Ok(Self {
+                    data: RawJavaString::from_byte_vec(string.into_bytes()),
+                })
+            }
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+
+        Err(NotAString { found })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::count_allocations;
+    use serde_json::json;
+
+    #[test]
+    fn json_macro() {
+        let short = JavaString::from("short");
+        let long = JavaString::from("a string that lives on the heap");
+
+        let value = json!({ "short": short, "long": &long, "list": [Value::from(long.clone())] });
+        assert_eq!(value["short"], "short");
+        assert_eq!(value["long"], "a string that lives on the heap");
+        assert_eq!(value["list"][0], "a string that lives on the heap");
+        assert_eq!(Value::from(short), Value::String("short".into()));
+    }
+
+    #[test]
+    fn try_from_each_variant() {
+        let string = JavaString::try_from(json!("string")).unwrap();
+        assert_eq!(string, "string");
+
+        let cases = [
+            (json!(null), "null"),
+            (json!(true), "boolean"),
+            (json!(1.5), "number"),
+            (json!(["string"]), "array"),
+            (json!({ "k": "string" }), "object"),
+        ];
+        for (value, found) in cases.iter() {
+            let err = JavaString::try_from(value.clone()).unwrap_err();
+            assert_eq!(err.found(), *found);
+            assert_eq!(
+                err.to_string(),
+                format!("expected a string, found {}", found)
+            );
+        }
+    }
+
+    #[test]
+    fn try_from_allocations() {
+        let value = json!("a string that lives on the heap");
+        let (string, allocations) = count_allocations(|| JavaString::try_from(value).unwrap());
+        assert_eq!(string, "a string that lives on the heap");
+        assert_eq!(allocations, 1);
+
+        let value = json!("short");
+        let (string, allocations) = count_allocations(|| JavaString::try_from(value).unwrap());
+        assert_eq!(string, "short");
+        assert_eq!(allocations, 0);
+    }
+}
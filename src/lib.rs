@@ -34,11 +34,53 @@ give credit to the documentation of standard `String`.
 
 extern crate alloc;
 extern crate serde;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(feature = "axum")]
+mod axum_impl;
+pub mod bytes;
+pub mod case_insensitive;
+#[cfg(feature = "encode")]
+pub mod encode;
+#[cfg(feature = "equivalent")]
+mod equivalent_impl;
+pub mod error;
+pub mod guard;
+pub mod io;
+pub mod iter;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod raw_string;
+pub mod redacted;
+#[cfg(feature = "segmentation")]
+mod segmentation;
+pub mod sort;
+pub mod wtf8;
 
+pub use bytes::JavaBytes;
+pub use case_insensitive::CaseInsensitive;
 use core::fmt;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Bound, Deref, DerefMut, RangeBounds};
+pub use error::BoundsError;
+pub use guard::MutVecGuard;
+pub use iter::{Drain, IntoChars};
 use raw_string::RawJavaString;
+pub use redacted::Redacted;
+
+/// How a `JavaString` is stored, as returned by [`JavaString::stats`].
+///
+/// [`JavaString::stats`]: struct.JavaString.html#method.stats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JavaStringStats {
+    /// The length of the string, in bytes.
+    pub len: usize,
+    /// Whether the string's bytes are stored inside the `JavaString` itself.
+    pub is_interned: bool,
+    /// The size of the string's heap allocation, or 0 if it's interned.
+    pub allocated_bytes: usize,
+}
 
 /// A UTF-8 encoded, immutable string.
 ///
@@ -145,6 +187,41 @@ impl JavaString {
         Ok(Self { data: raw_str })
     }
 
+    /// Takes the contents of `bytes` and converts them to a `JavaString`, if
+    /// they're valid UTF-8.
+    ///
+    /// On success, the contents are copied into the new `JavaString` (which
+    /// interns them if they're short enough), the buffer of `bytes` is freed,
+    /// and `bytes` is left empty. On failure, `bytes` is left untouched, so the
+    /// caller can repair it and try again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the bytes are not UTF-8 with a description as to why the
+    /// provided bytes are not UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut bytes = vec![0, 159, 146, 150];
+    /// assert!(JavaString::from_utf8_mut(&mut bytes).is_err());
+    ///
+    /// bytes[0] = 240;
+    /// let sparkle_heart = JavaString::from_utf8_mut(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(sparkle_heart, "💖");
+    /// assert!(bytes.is_empty());
+    /// ```
+    pub fn from_utf8_mut(bytes: &mut Vec<u8>) -> Result<Self, core::str::Utf8Error> {
+        core::str::from_utf8(bytes)?;
+        Ok(Self {
+            data: RawJavaString::from_byte_vec(core::mem::take(bytes)),
+        })
+    }
+
     /// Included for API compatibility.
     ///
     /// Calls to the `String` member function of the same name.
@@ -152,37 +229,391 @@ impl JavaString {
         String::from_utf8_lossy(v)
     }
 
+    /// Converts a slice of bytes to a `JavaString`, replacing each invalid
+    /// sequence with U+FFFD REPLACEMENT CHARACTER, like [`from_utf8_lossy`].
+    ///
+    /// Unlike [`from_utf8_lossy`], the result is always a `JavaString`, built
+    /// directly from the valid parts of `v` with at most one allocation for
+    /// the string itself.
+    ///
+    /// [`from_utf8_lossy`]: struct.JavaString.html#method.from_utf8_lossy
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from_utf8_lossy_owned(b"Hello \xF0\x90\x80World");
+    ///
+    /// assert_eq!(s, "Hello \u{FFFD}World");
+    /// ```
+    pub fn from_utf8_lossy_owned(v: &[u8]) -> JavaString {
+        Self::from_utf8_replace(v, char::REPLACEMENT_CHARACTER)
+    }
+
+    /// Converts a slice of bytes to a `JavaString`, like [`from_utf8_lossy`],
+    /// but replaces each invalid sequence with `replacement` instead of
+    /// U+FFFD. The bytes are copied straight into the new string.
+    ///
+    /// [`from_utf8_lossy`]: struct.JavaString.html#method.from_utf8_lossy
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from_utf8_replace(b"caf\xe9 \xf0\x9f", '?');
+    ///
+    /// assert_eq!(s, "caf? ?");
+    /// ```
+    pub fn from_utf8_replace(bytes: &[u8], replacement: char) -> JavaString {
+        if let Ok(text) = core::str::from_utf8(bytes) {
+            return Self::from(text);
+        }
+
+        let mut buf = [0; 4];
+        let replacement = replacement.encode_utf8(&mut buf).as_bytes();
+
+        let mut pieces = Vec::new();
+        for chunk in bytes.utf8_chunks() {
+            pieces.push(chunk.valid().as_bytes());
+            if !chunk.invalid().is_empty() {
+                pieces.push(replacement);
+            }
+        }
+
+        Self {
+            data: RawJavaString::from_bytes_array(pieces),
+        }
+    }
+
     /// Decode a UTF-16 encoded vector `v` into a `JavaString`, returning `Err`
     /// if `v` contains any invalid data.
     ///
-    /// May cause memory leaks depending on how your allocator is configured.
+    /// The input is decoded straight into the new string, which takes at most
+    /// one allocation.
     pub fn from_utf16(v: &[u16]) -> Result<Self, alloc::string::FromUtf16Error> {
-        Ok(String::from_utf16(v)?.into())
+        let chars = char::decode_utf16(v.iter().copied());
+        if chars.clone().any(|ch| ch.is_err()) {
+            return Err(String::from_utf16(v).unwrap_err());
+        }
+        Ok(Self::from_char_iter(chars.map(Result::unwrap)))
+    }
+
+    /// Decode a UTF-16 encoded slice `v` into a `JavaString`, replacing invalid
+    /// data with the replacement character (U+FFFD).
+    ///
+    /// Like [`from_utf16`], the input is decoded straight into the new string.
+    ///
+    /// [`from_utf16`]: struct.JavaString.html#method.from_utf16
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0xD834, 0xDD1E, 0x006d, 0x0075,
+    ///           0x0073, 0xDD1E, 0x0069, 0x0063,
+    ///           0xD834];
+    ///
+    /// assert_eq!(JavaString::from_utf16_lossy(v), "𝄞mus\u{FFFD}ic\u{FFFD}");
+    /// ```
+    pub fn from_utf16_lossy(v: &[u16]) -> JavaString {
+        Self::from_char_iter(
+            char::decode_utf16(v.iter().copied())
+                .map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER)),
+        )
+    }
+
+    /// Decode a UTF-16LE encoded byte slice `v` into a `JavaString`, returning
+    /// `Err` if `v` has an odd length or contains any invalid data.
+    ///
+    /// A byte order mark isn't treated specially, so a leading one ends up in
+    /// the string as U+FEFF.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// // 𝄞music
+    /// let v = &[0x34, 0xD8, 0x1E, 0xDD, 0x6d, 0x00, 0x75, 0x00,
+    ///           0x73, 0x00, 0x69, 0x00, 0x63, 0x00];
+    /// assert_eq!(JavaString::from_utf16le(v).unwrap(), "𝄞music");
+    ///
+    /// // 𝄞mu<invalid>ic
+    /// let v = &[0x34, 0xD8, 0x1E, 0xDD, 0x6d, 0x00, 0x75, 0x00,
+    ///           0x00, 0xD8, 0x69, 0x00, 0x63, 0x00];
+    /// assert!(JavaString::from_utf16le(v).is_err());
+    /// ```
+    pub fn from_utf16le(v: &[u8]) -> Result<Self, alloc::string::FromUtf16Error> {
+        Self::from_utf16_bytes(v, u16::from_le_bytes)
+    }
+
+    /// Decode a UTF-16LE encoded byte slice `v` into a `JavaString`, replacing
+    /// invalid data with the replacement character (U+FFFD). A trailing odd
+    /// byte is replaced too.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0x34, 0xD8, 0x1E, 0xDD, 0x6d, 0x00, 0x75, 0x00,
+    ///           0x73, 0x00, 0x1E, 0xDD, 0x69, 0x00, 0x63, 0x00,
+    ///           0x34, 0xD8];
+    ///
+    /// assert_eq!(JavaString::from_utf16le_lossy(v), "𝄞mus\u{FFFD}ic\u{FFFD}");
+    /// ```
+    pub fn from_utf16le_lossy(v: &[u8]) -> JavaString {
+        Self::from_utf16_bytes_lossy(v, u16::from_le_bytes)
+    }
+
+    /// Decode a UTF-16BE encoded byte slice `v` into a `JavaString`, returning
+    /// `Err` if `v` has an odd length or contains any invalid data.
+    ///
+    /// A byte order mark isn't treated specially, so a leading one ends up in
+    /// the string as U+FEFF.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// // 𝄞music
+    /// let v = &[0xD8, 0x34, 0xDD, 0x1E, 0x00, 0x6d, 0x00, 0x75,
+    ///           0x00, 0x73, 0x00, 0x69, 0x00, 0x63];
+    /// assert_eq!(JavaString::from_utf16be(v).unwrap(), "𝄞music");
+    ///
+    /// // An odd number of bytes is never valid.
+    /// assert!(JavaString::from_utf16be(&[0x00, 0x6d, 0x00]).is_err());
+    /// ```
+    pub fn from_utf16be(v: &[u8]) -> Result<Self, alloc::string::FromUtf16Error> {
+        Self::from_utf16_bytes(v, u16::from_be_bytes)
+    }
+
+    /// Decode a UTF-16BE encoded byte slice `v` into a `JavaString`, replacing
+    /// invalid data with the replacement character (U+FFFD). A trailing odd
+    /// byte is replaced too.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// // 𝄞mus<invalid>ic<invalid>
+    /// let v = &[0xD8, 0x34, 0xDD, 0x1E, 0x00, 0x6d, 0x00, 0x75,
+    ///           0x00, 0x73, 0xDD, 0x1E, 0x00, 0x69, 0x00, 0x63,
+    ///           0xD8, 0x34];
+    ///
+    /// assert_eq!(JavaString::from_utf16be_lossy(v), "𝄞mus\u{FFFD}ic\u{FFFD}");
+    /// ```
+    pub fn from_utf16be_lossy(v: &[u8]) -> JavaString {
+        Self::from_utf16_bytes_lossy(v, u16::from_be_bytes)
+    }
+
+    /// Returns the UTF-16 code units in `v`, read with `from_bytes`. A trailing
+    /// odd byte is left out.
+    fn utf16_units(
+        v: &[u8],
+        from_bytes: fn([u8; 2]) -> u16,
+    ) -> impl Iterator<Item = u16> + Clone + '_ {
+        v.chunks_exact(2)
+            .map(move |pair| from_bytes([pair[0], pair[1]]))
+    }
+
+    fn from_utf16_bytes(
+        v: &[u8],
+        from_bytes: fn([u8; 2]) -> u16,
+    ) -> Result<Self, alloc::string::FromUtf16Error> {
+        let chars = char::decode_utf16(Self::utf16_units(v, from_bytes));
+        if v.len() % 2 == 1 || chars.clone().any(|ch| ch.is_err()) {
+            // `FromUtf16Error` can't be built directly, and carries no details.
+            return Err(String::from_utf16(&[0xD800]).unwrap_err());
+        }
+        Ok(Self::from_char_iter(chars.map(Result::unwrap)))
+    }
+
+    fn from_utf16_bytes_lossy(v: &[u8], from_bytes: fn([u8; 2]) -> u16) -> JavaString {
+        let trailing = if v.len() % 2 == 1 {
+            Some(char::REPLACEMENT_CHARACTER)
+        } else {
+            None
+        };
+        Self::from_char_iter(
+            char::decode_utf16(Self::utf16_units(v, from_bytes))
+                .map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .chain(trailing),
+        )
+    }
+
+    /// Builds a `JavaString` out of `chars` in two passes: one to measure it,
+    /// and one to encode it straight into the new string.
+    fn from_char_iter(chars: impl Iterator<Item = char> + Clone) -> JavaString {
+        let len = chars.clone().map(char::len_utf8).sum();
+        let mut data = RawJavaString::zeroed(len);
+
+        let mut bytes = data.get_bytes_mut();
+        for ch in chars {
+            let (encoded, rest) = bytes.split_at_mut(ch.len_utf8());
+            ch.encode_utf8(encoded);
+            bytes = rest;
+        }
+
+        Self { data }
     }
 
     /// Converts a vector of bytes to a `JavaString` without checking that the string
     /// contains valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The bytes passed in must be valid UTF-8.
     pub unsafe fn from_utf8_unchecked(bytes: Vec<u8>) -> JavaString {
         String::from_utf8_unchecked(bytes).into()
     }
 
     /// Converts a `JavaString` into a byte vector.
     ///
-    /// This consumes the `String`, so we do not need to copy its contents.
+    /// The contents are copied into a `Vec` of exactly the right size, since a
+    /// `JavaString`'s buffer can't be handed over to a `Vec`. Use
+    /// [`into_java_bytes`] to take the bytes without copying them.
+    ///
+    /// [`into_java_bytes`]: struct.JavaString.html#method.into_java_bytes
     pub fn into_bytes(self) -> Vec<u8> {
         self.data.into_bytes()
     }
 
+    /// Converts this `JavaString` into its bytes without copying them.
+    ///
+    /// Heap buffers are handed over as they are, keeping their address, and
+    /// short strings stay interned, so this never allocates. Use
+    /// [`into_bytes`] when a `Vec<u8>` is needed instead.
+    ///
+    /// [`into_bytes`]: struct.JavaString.html#method.into_bytes
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("a string that lives on the heap");
+    /// let ptr = s.as_ptr();
+    ///
+    /// let bytes = s.into_java_bytes();
+    /// assert_eq!(bytes.as_ptr(), ptr);
+    /// assert_eq!(&bytes[..], b"a string that lives on the heap");
+    /// ```
+    pub fn into_java_bytes(self) -> JavaBytes {
+        JavaBytes::new(self.data)
+    }
+
+    /// Converts this `JavaString` into a `String`.
+    ///
+    /// Like [`into_bytes`], this copies the contents into a new allocation of
+    /// exactly the right size.
+    ///
+    /// [`into_bytes`]: struct.JavaString.html#method.into_bytes
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("hello");
+    ///
+    /// assert_eq!(s.into_string(), String::from("hello"));
+    /// ```
+    pub fn into_string(self) -> String {
+        unsafe { String::from_utf8_unchecked(self.into_bytes()) }
+    }
+
+    /// Converts this `JavaString` into a [`Box`]`<`[`str`]`>`.
+    ///
+    /// Like [`into_bytes`], this copies the contents into a new allocation of
+    /// exactly the right size.
+    ///
+    /// [`Box`]: ../../std/boxed/struct.Box.html
+    /// [`str`]: ../../std/primitive.str.html
+    /// [`into_bytes`]: struct.JavaString.html#method.into_bytes
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("hello");
+    ///
+    /// let b = s.into_boxed_str();
+    /// assert_eq!(&*b, "hello");
+    /// ```
+    pub fn into_boxed_str(self) -> alloc::boxed::Box<str> {
+        self.into_string().into_boxed_str()
+    }
+
     /// Extracts a string slice containing the entire `JavaString`.
+    ///
+    /// With the `debug_checks` feature, debug builds panic here if an unsafe
+    /// API was used to put invalid UTF-8 into the string.
     pub fn as_str(&self) -> &str {
+        self.debug_check_utf8();
         unsafe { core::str::from_utf8_unchecked(self.data.get_bytes()) }
     }
 
     /// Extracts a mutable string slice containing the entire `JavaString`.
+    ///
+    /// With the `debug_checks` feature, debug builds panic here if an unsafe
+    /// API was used to put invalid UTF-8 into the string.
     pub fn as_mut_str(&mut self) -> &mut str {
+        self.debug_check_utf8();
         unsafe { core::str::from_utf8_unchecked_mut(self.data.get_bytes_mut()) }
     }
 
+    /// Asserts that the contents are valid UTF-8, in debug builds with the
+    /// `debug_checks` feature. Does nothing otherwise.
+    #[inline(always)]
+    fn debug_check_utf8(&self) {
+        #[cfg(feature = "debug_checks")]
+        debug_assert!(
+            core::str::from_utf8(self.data.get_bytes()).is_ok(),
+            "JavaString contains invalid UTF-8: {:?}",
+            self.data
+        );
+    }
+
+    /// Concatenates two string slices into a new `JavaString`, copying each
+    /// exactly once. The result is interned if it's short enough, and takes a
+    /// single allocation otherwise.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::concat_pair("foo", "bar");
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    pub fn concat_pair(a: &str, b: &str) -> JavaString {
+        let sl: &[_] = &[a.as_bytes(), b.as_bytes()];
+        Self {
+            data: RawJavaString::from_bytes_array(sl),
+        }
+    }
+
     /// Appends a given string slice onto the end of this `JavaString`.
     ///
     ///# Examples
@@ -200,16 +631,74 @@ impl JavaString {
     /// assert_eq!(s, "foobarfoobar");
     /// ```
     pub fn push_str(&mut self, string: &str) {
-        let sl: &[_] = &[self.as_bytes(), string.as_bytes()];
+        *self = Self::concat_pair(self, string);
+    }
+
+    /// Inserts a given string slice at the front of this `JavaString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("bar");
+    ///
+    /// s.prepend("foo");
+    /// assert_eq!(s, "foobar");
+    /// ```
+    pub fn prepend(&mut self, string: &str) {
+        let sl: &[_] = &[string.as_bytes(), self.as_bytes()];
         self.data = RawJavaString::from_bytes_array(sl);
     }
 
+    /// Inserts the given `char` at the front of this `JavaString`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    ///
+    /// s.prepend_char('$');
+    /// assert_eq!(s, "$foo");
+    /// ```
+    pub fn prepend_char(&mut self, ch: char) {
+        self.prepend(ch.encode_utf8(&mut [0; 4]))
+    }
+
     /// Returns this `JavaString`'s capacity, in bytes. Always returns the
     /// same value as `self.len()`.
     pub fn capacity(&self) -> usize {
         self.len()
     }
 
+    /// Returns a snapshot of how this `JavaString` is stored, for aggregating
+    /// how often strings in a workload stay interned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let stats = JavaString::from("foo").stats();
+    ///
+    /// assert_eq!(stats.len, 3);
+    /// assert!(stats.is_interned);
+    /// assert_eq!(stats.allocated_bytes, 0);
+    /// ```
+    pub fn stats(&self) -> JavaStringStats {
+        let is_interned = self.data.is_interned();
+        JavaStringStats {
+            len: self.len(),
+            is_interned,
+            allocated_bytes: if is_interned { 0 } else { self.len() },
+        }
+    }
+
     /// Included for API compatibility with standard `String` implementation.
     ///
     /// Does nothing.
@@ -223,28 +712,27 @@ impl JavaString {
     /// Included for API compatibility with standard `String` implementation.
     ///
     /// Does nothing.
-    #[cfg(nightly)]
     pub fn try_reserve(
         &mut self,
         _additional: usize,
-    ) -> Result<(), std::collections::CollectionAllocErr> {
+    ) -> Result<(), alloc::collections::TryReserveError> {
         Ok(())
     }
 
     /// Included for API compatibility with standard `String` implementation.
     ///
     /// Does nothing.
-    #[cfg(nightly)]
     pub fn try_reserve_exact(
         &mut self,
-        additional: usize,
-    ) -> Result<(), std::collections::CollectionAllocErr> {
+        _additional: usize,
+    ) -> Result<(), alloc::collections::TryReserveError> {
         Ok(())
     }
 
     /// Included for API compatibility with standard `String` implementation.
     ///
-    /// Does nothing.
+    /// Does nothing. A `JavaString` never holds more memory than it needs, so
+    /// any string that's short enough to be interned already is.
     pub fn shrink_to_fit(&mut self) {}
 
     /// Included for API compatibility with standard `String` implementation.
@@ -256,7 +744,7 @@ impl JavaString {
     /// standard String version, this method has runtime that's linear with the
     /// length of the string.
     ///
-    ///# Examples
+    /// # Examples
     ///
     /// Basic usage:
     ///
@@ -264,12 +752,17 @@ impl JavaString {
     /// # use jstring::JavaString;
     /// let mut s = JavaString::from("foo");
     ///
-    /// assert_eq!(s.pop(), Some('o'));
-    /// assert_eq!(s.pop(), Some('o'));
-    /// assert_eq!(s.pop(), Some('f'));
+    /// s.push('b');
+    /// assert_eq!(s, "foob");
+    ///
+    /// s.push('a');
+    /// assert_eq!(s, "fooba");
+    ///
+    /// s.push('r');
+    /// assert_eq!(s, "foobar");
     /// ```
     pub fn push(&mut self, ch: char) {
-        self.push_str(&ch.to_string())
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
     }
 
     /// Returns a byte slice of this `JavaString`'s contents.
@@ -278,8 +771,8 @@ impl JavaString {
         self.data.get_bytes()
     }
 
-    /// Shortens this String to the specified length. Unlike the standard String
-    /// version, this method has runtime that's linear with the length of the string.
+    /// Returns whether this `JavaString`'s bytes are exactly `other`. Unlike
+    /// comparing against a `str`, `other` doesn't need to be valid UTF-8.
     ///
     /// # Examples
     ///
@@ -287,24 +780,50 @@ impl JavaString {
     ///
     /// ```
     /// # use jstring::JavaString;
-    /// let mut s = JavaString::from("foo");
-    ///
-    /// s.push('b');
-    /// assert_eq!(s, "foob");
+    /// let s = JavaString::from("GET");
     ///
-    /// s.push('a');
-    /// assert_eq!(s, "fooba");
-    ///
-    /// s.push('r');
-    /// assert_eq!(s, "foobar");
+    /// assert!(s.bytes_eq(b"GET"));
+    /// assert!(!s.bytes_eq(b"GET\xff"));
     /// ```
-    pub fn truncate(&mut self, new_len: usize) {
-        self.data = RawJavaString::from_bytes(&self.as_bytes()[0..new_len]);
+    pub fn bytes_eq(&self, other: &[u8]) -> bool {
+        self.len() == other.len() && self.as_bytes() == other
     }
 
-    /// Removes the last character from the string buffer and returns it.
-    /// Returns `None` if this String is empty. Unlike the standard String version,
-    /// this method has runtime that's linear with the length of the string.
+    /// Shortens this String to the specified length. Unlike the standard String
+    /// version, this method has runtime that's linear with the length of the string.
+    ///
+    /// If `new_len` is greater than or equal to the string's current length,
+    /// this has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` is less than the string's length and does not lie
+    /// on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("hello");
+    ///
+    /// s.truncate(2);
+    /// assert_eq!(s, "he");
+    ///
+    /// s.truncate(10);
+    /// assert_eq!(s, "he");
+    /// ```
+    #[track_caller]
+    pub fn truncate(&mut self, new_len: usize) {
+        unwrap_bounds(self.checked_truncate(new_len))
+    }
+
+    /// Removes the last character from the string buffer and returns it.
+    /// Returns `None` if this String is empty. Unlike the standard String version,
+    /// this method has runtime that's linear with the length of the string.
     ///
     /// # Examples
     ///
@@ -317,9 +836,11 @@ impl JavaString {
     /// assert_eq!(s.pop(), Some('o'));
     /// assert_eq!(s.pop(), Some('o'));
     /// assert_eq!(s.pop(), Some('f'));
+    ///
+    /// assert_eq!(s.pop(), None);
     /// ```
     pub fn pop(&mut self) -> Option<char> {
-        let ch = self.chars().rev().next()?;
+        let ch = self.chars().next_back()?;
         let newlen = self.len() - ch.len_utf8();
         self.data = RawJavaString::from_bytes(&self.as_bytes()[0..newlen]);
         Some(ch)
@@ -349,115 +870,3994 @@ impl JavaString {
     /// assert_eq!(s.remove(1), 'o');
     /// assert_eq!(s.remove(0), 'o');
     /// ```
+    #[track_caller]
     pub fn remove(&mut self, idx: usize) -> char {
-        let ch = match self[idx..].chars().next() {
-            Some(ch) => ch,
-            None => panic!("cannot remove a char from the end of a string"),
+        unwrap_bounds(self.checked_remove(idx))
+    }
+
+    /// Replaces the `char_idx`-th [`char`] of this `JavaString` with `ch`, and
+    /// returns the character that was there before. Returns `None` if the string
+    /// has `char_idx` characters or fewer.
+    ///
+    /// The string is only rebuilt if `ch` has a different length in UTF-8 than
+    /// the character it replaces; otherwise, its bytes are overwritten in place.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("héllo");
+    ///
+    /// assert_eq!(s.replace_char(1, 'e'), Some('é'));
+    /// assert_eq!(s, "hello");
+    /// assert_eq!(s.replace_char(5, '!'), None);
+    /// ```
+    pub fn replace_char(&mut self, char_idx: usize, ch: char) -> Option<char> {
+        let (idx, old) = self.char_indices().nth(char_idx)?;
+        let next = idx + old.len_utf8();
+        let mut buf = [0; 4];
+        let encoded = ch.encode_utf8(&mut buf).as_bytes();
+
+        if encoded.len() == old.len_utf8() {
+            self.data.get_bytes_mut()[idx..next].copy_from_slice(encoded);
+        } else {
+            let bytes_array: &[&[u8]] =
+                &[&self.as_bytes()[0..idx], encoded, &self.as_bytes()[next..]];
+            self.data = RawJavaString::from_bytes_array(bytes_array);
+        }
+
+        Some(old)
+    }
+
+    /// Returns the number of lines in this `JavaString`, counted the same way
+    /// as `str::lines` does. A trailing newline doesn't start a new line.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// assert_eq!(JavaString::from("foo\nbar\n").count_lines(), 2);
+    /// assert_eq!(JavaString::from("foo\nbar").count_lines(), 2);
+    /// assert_eq!(JavaString::new().count_lines(), 0);
+    /// ```
+    pub fn count_lines(&self) -> usize {
+        match self.as_bytes().last() {
+            None => 0,
+            Some(&last) => {
+                let newlines = self.bytes().filter(|&b| b == b'\n').count();
+                newlines + (last != b'\n') as usize
+            }
+        }
+    }
+
+    /// Returns the last line of this `JavaString`, i.e. the last item that
+    /// `str::lines` would return, or `""` if there are no lines. Only the end of
+    /// the string is scanned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// assert_eq!(JavaString::from("foo\r\nbar\r\n").last_line(), "bar");
+    /// assert_eq!(JavaString::from("foo\nbar").last_line(), "bar");
+    /// ```
+    pub fn last_line(&self) -> &str {
+        let text = match self.strip_suffix('\n') {
+            Some(text) => text.strip_suffix('\r').unwrap_or(text),
+            None => self.as_str(),
         };
 
-        let next = idx + ch.len_utf8();
-        let bytes_array: &[&[u8]] = &[&self.as_bytes()[0..idx], &self.as_bytes()[next..]];
-        self.data = RawJavaString::from_bytes_array(bytes_array);
-        ch
+        match text.rfind('\n') {
+            Some(idx) => &text[idx + 1..],
+            None => text,
+        }
     }
-}
 
-impl fmt::Display for JavaString {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let jstr: &str = &*self;
-        jstr.fmt(formatter)
+    /// Shortens this `JavaString` to at most `max_bytes` bytes. If `max_bytes`
+    /// falls inside a character, that whole character is removed too, so this
+    /// never panics and always leaves valid UTF-8 behind.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("a💖b");
+    ///
+    /// s.truncate_to_byte_boundary(3);
+    /// assert_eq!(s, "a");
+    /// ```
+    pub fn truncate_to_byte_boundary(&mut self, max_bytes: usize) {
+        if max_bytes >= self.len() {
+            return;
+        }
+
+        let mut new_len = max_bytes;
+        while !self.is_char_boundary(new_len) {
+            new_len -= 1;
+        }
+        self.truncate(new_len);
     }
-}
 
-impl fmt::Debug for JavaString {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let jstr: &str = &*self;
-        jstr.fmt(formatter)
+    /// Removes the specified range from the string in bulk, returning all
+    /// removed characters as an iterator.
+    ///
+    /// The returned iterator keeps a mutable borrow on the string. The range is
+    /// removed when the iterator is dropped, even if it hasn't been fully
+    /// consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point or end point do not lie on a [`char`]
+    /// boundary, or if they're out of bounds.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("α is alpha, β is beta");
+    /// let beta_offset = s.find('β').unwrap_or(s.len());
+    ///
+    /// // Remove the range up until the β from the string
+    /// let t: String = s.drain(..beta_offset).collect();
+    /// assert_eq!(t, "α is alpha, ");
+    /// assert_eq!(s, "β is beta");
+    ///
+    /// // A full range clears the string
+    /// s.drain(..);
+    /// assert_eq!(s, "");
+    /// ```
+    #[track_caller]
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_> {
+        let (start, end) = unwrap_bounds(self.check_range(range));
+        Drain::new(self, start, end)
     }
-}
 
-impl Deref for JavaString {
-    type Target = str;
-    fn deref(&self) -> &str {
-        self.as_str()
+    /// Converts this `JavaString` into an iterator over its `char`s. The
+    /// buffer is freed once the iterator is exhausted or dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("abc");
+    ///
+    /// assert_eq!(s.into_chars().rev().collect::<String>(), "cba");
+    /// ```
+    pub fn into_chars(self) -> IntoChars {
+        IntoChars::new(self.data)
     }
-}
 
-impl DerefMut for JavaString {
-    fn deref_mut(&mut self) -> &mut str {
+    /// Returns a mutable string slice of this `JavaString`'s contents.
+    ///
+    /// Included for parity with `Arc::make_mut`. Every `JavaString` uniquely
+    /// owns its buffer, so there's never anything to clone, and this is the same
+    /// as [`as_mut_str`].
+    ///
+    /// [`as_mut_str`]: struct.JavaString.html#method.as_mut_str
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    /// let t = s.clone();
+    ///
+    /// s.make_mut().make_ascii_uppercase();
+    /// assert_eq!(s, "FOO");
+    /// assert_eq!(t, "foo");
+    /// ```
+    pub fn make_mut(&mut self) -> &mut str {
         self.as_mut_str()
     }
-}
 
-impl From<String> for JavaString {
-    fn from(string: String) -> Self {
-        Self {
-            data: RawJavaString::from_byte_vec(string.into_bytes()),
+    /// Creates a `JavaString` made of up to `n` copies of `string`, stopping
+    /// before the result would be longer than `max_bytes`. Only whole copies
+    /// of `string` are ever included.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// assert_eq!(JavaString::from_str_repeat_capped("ab", 3, 5), "abab");
+    /// assert_eq!(JavaString::from_str_repeat_capped("ab", 3, 100), "ababab");
+    /// ```
+    pub fn from_str_repeat_capped(string: &str, n: usize, max_bytes: usize) -> JavaString {
+        if string.is_empty() {
+            return Self::new();
         }
-    }
-}
 
-impl<'a> From<&'a str> for JavaString {
-    fn from(string: &'a str) -> Self {
-        Self {
-            data: RawJavaString::from_bytes(string.as_bytes()),
+        let copies = (max_bytes / string.len()).min(n);
+        let mut data = RawJavaString::zeroed(copies * string.len());
+        for chunk in data.get_bytes_mut().chunks_exact_mut(string.len()) {
+            chunk.copy_from_slice(string.as_bytes());
         }
+        Self { data }
     }
-}
 
-impl PartialOrd for JavaString {
-    fn partial_cmp(&self, rhs: &Self) -> Option<core::cmp::Ordering> {
-        let jstr: &str = &*self;
-        jstr.partial_cmp(rhs)
+    /// Takes the contents out of this `JavaString`, leaving it empty. The same
+    /// as `core::mem::take(self)`; the buffer just changes owners, so nothing
+    /// is copied or freed.
+    ///
+    /// To take a string out of an `Option<JavaString>`, use `Option::take`
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    ///
+    /// assert_eq!(s.take(), "foo");
+    /// assert_eq!(s, "");
+    /// ```
+    pub fn take(&mut self) -> JavaString {
+        core::mem::take(self)
     }
-}
 
-impl<'a> PartialEq<str> for &'a JavaString {
-    fn eq(&self, rhs: &str) -> bool {
-        let jstr: &str = &*self;
-        jstr.eq(rhs)
+    /// Replaces the contents of this `JavaString` with `new`, and returns the
+    /// old contents. The same as `core::mem::replace(self, new)`.
+    ///
+    /// This isn't called `replace`, since that would hide `str::replace`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    ///
+    /// assert_eq!(s.replace_contents(JavaString::from("bar")), "foo");
+    /// assert_eq!(s, "bar");
+    /// ```
+    pub fn replace_contents(&mut self, new: JavaString) -> JavaString {
+        core::mem::replace(self, new)
     }
-}
 
-impl<'a> PartialEq<&'a str> for JavaString {
-    fn eq(&self, rhs: &&'a str) -> bool {
-        let jstr: &str = &*self;
-        let jstr_2 = &jstr;
-        jstr_2.eq(rhs)
+    /// Returns an error if `idx` is past the end of this `JavaString`, or isn't
+    /// on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    fn check_index(&self, idx: usize) -> Result<(), BoundsError> {
+        if idx > self.len() {
+            Err(BoundsError::OutOfBounds {
+                index: idx,
+                len: self.len(),
+            })
+        } else if !self.is_char_boundary(idx) {
+            Err(BoundsError::NotCharBoundary { index: idx })
+        } else {
+            Ok(())
+        }
     }
-}
 
-impl PartialEq<str> for JavaString {
-    fn eq(&self, rhs: &str) -> bool {
-        let jstr: &str = &*self;
-        jstr.eq(rhs)
+    /// Resolves `range` into a start and end index, returning an error if
+    /// either can't be used, or if the range is backwards.
+    fn check_range(&self, range: impl RangeBounds<usize>) -> Result<(usize, usize), BoundsError> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+
+        if start > end {
+            return Err(BoundsError::InvalidRange { start, end });
+        }
+
+        self.check_index(end)?;
+        self.check_index(start)?;
+        Ok((start, end))
     }
-}
 
-impl Ord for JavaString {
-    fn cmp(&self, rhs: &Self) -> core::cmp::Ordering {
-        let jstr: &str = &*self;
-        jstr.cmp(rhs)
+    /// Shortens this `JavaString` to the specified length, like [`truncate`],
+    /// but returns an error instead of panicking, leaving the string untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `new_len` is less than the string's length and does not
+    /// lie on a [`char`] boundary.
+    ///
+    /// [`truncate`]: struct.JavaString.html#method.truncate
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn checked_truncate(&mut self, new_len: usize) -> Result<(), BoundsError> {
+        if new_len >= self.len() {
+            return Ok(());
+        }
+        self.check_index(new_len)?;
+        self.data = RawJavaString::from_bytes(&self.as_bytes()[0..new_len]);
+        Ok(())
     }
-}
 
-impl serde::Serialize for JavaString {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let jstr: &str = &*self;
-        jstr.serialize(serializer)
+    /// Removes a [`char`] from this `JavaString` at a byte position and returns
+    /// it, like [`remove`], but returns an error instead of panicking, leaving
+    /// the string untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `idx` is larger than or equal to the string's length,
+    /// or if it does not lie on a [`char`] boundary.
+    ///
+    /// [`remove`]: struct.JavaString.html#method.remove
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::{BoundsError, JavaString};
+    /// let mut s = JavaString::from("héllo");
+    ///
+    /// assert_eq!(s.checked_remove(2), Err(BoundsError::NotCharBoundary { index: 2 }));
+    /// assert_eq!(s.checked_remove(1), Ok('é'));
+    /// assert_eq!(s, "hllo");
+    /// ```
+    pub fn checked_remove(&mut self, idx: usize) -> Result<char, BoundsError> {
+        if idx >= self.len() {
+            return Err(BoundsError::OutOfBounds {
+                index: idx,
+                len: self.len(),
+            });
+        }
+        self.check_index(idx)?;
+
+        let ch = self[idx..].chars().next().unwrap();
+        let next = idx + ch.len_utf8();
+        let bytes_array: &[&[u8]] = &[&self.as_bytes()[0..idx], &self.as_bytes()[next..]];
+        self.data = RawJavaString::from_bytes_array(bytes_array);
+        Ok(ch)
     }
-}
 
-impl<'de> serde::Deserialize<'de> for JavaString {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        Ok(Self {
-            data: RawJavaString::from_byte_vec(String::deserialize(deserializer)?.into_bytes()),
-        })
+    /// Inserts a character into this `JavaString` at a byte position.
+    ///
+    /// This is an `O(n)` operation, as it requires copying every element in the
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the `JavaString`'s length, or if it does
+    /// not lie on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::new();
+    ///
+    /// s.insert(0, 'f');
+    /// s.insert(1, 'o');
+    /// s.insert(2, 'o');
+    ///
+    /// assert_eq!(s, "foo");
+    /// ```
+    #[track_caller]
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        unwrap_bounds(self.checked_insert(idx, ch))
+    }
+
+    /// Inserts a character into this `JavaString` at a byte position, like
+    /// [`insert`], but returns an error instead of panicking, leaving the string
+    /// untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `idx` is larger than the string's length, or if it does
+    /// not lie on a [`char`] boundary.
+    ///
+    /// [`insert`]: struct.JavaString.html#method.insert
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn checked_insert(&mut self, idx: usize, ch: char) -> Result<(), BoundsError> {
+        self.checked_insert_str(idx, ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Inserts a string slice into this `JavaString` at a byte position.
+    ///
+    /// This is an `O(n)` operation, as it requires copying every element in the
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the `JavaString`'s length, or if it does
+    /// not lie on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("bar");
+    ///
+    /// s.insert_str(0, "foo");
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    #[track_caller]
+    pub fn insert_str(&mut self, idx: usize, string: &str) {
+        unwrap_bounds(self.checked_insert_str(idx, string))
+    }
+
+    /// Inserts a string slice into this `JavaString` at a byte position, like
+    /// [`insert_str`], but returns an error instead of panicking, leaving the
+    /// string untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `idx` is larger than the string's length, or if it does
+    /// not lie on a [`char`] boundary.
+    ///
+    /// [`insert_str`]: struct.JavaString.html#method.insert_str
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn checked_insert_str(&mut self, idx: usize, string: &str) -> Result<(), BoundsError> {
+        self.checked_replace_range(idx..idx, string)
+    }
+
+    /// Removes the specified range in the `JavaString`, and replaces it with the
+    /// given string. The given string doesn't need to be the same length as the
+    /// range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point or end point do not lie on a [`char`]
+    /// boundary, or if they're out of bounds.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("α is alpha, β is beta");
+    /// let beta_offset = s.find('β').unwrap_or(s.len());
+    ///
+    /// // Replace the range up until the β from the string
+    /// s.replace_range(..beta_offset, "Α is capital alpha; ");
+    /// assert_eq!(s, "Α is capital alpha; β is beta");
+    /// ```
+    #[track_caller]
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, replace_with: &str) {
+        unwrap_bounds(self.checked_replace_range(range, replace_with))
+    }
+
+    /// Removes the specified range in the `JavaString`, and replaces it with the
+    /// given string, like [`replace_range`], but returns an error instead of
+    /// panicking, leaving the string untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the starting point or end point do not lie on a
+    /// [`char`] boundary, if they're out of bounds, or if the range starts after
+    /// it ends.
+    ///
+    /// [`replace_range`]: struct.JavaString.html#method.replace_range
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn checked_replace_range<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        replace_with: &str,
+    ) -> Result<(), BoundsError> {
+        let (start, end) = self.check_range(range)?;
+        let bytes_array: &[&[u8]] = &[
+            &self.as_bytes()[0..start],
+            replace_with.as_bytes(),
+            &self.as_bytes()[end..],
+        ];
+        self.data = RawJavaString::from_bytes_array(bytes_array);
+        Ok(())
+    }
+
+    /// Splits the string into two at the given byte index.
+    ///
+    /// Returns a newly allocated `JavaString`. `self` contains bytes `[0, at)`,
+    /// and the returned `JavaString` contains bytes `[at, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is not on a [`char`] boundary, or if it is beyond the last
+    /// code point of the string.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut hello = JavaString::from("Hello, World!");
+    /// let world = hello.split_off(7);
+    /// assert_eq!(hello, "Hello, ");
+    /// assert_eq!(world, "World!");
+    /// ```
+    #[track_caller]
+    pub fn split_off(&mut self, at: usize) -> JavaString {
+        unwrap_bounds(self.checked_split_off(at))
+    }
+
+    /// Splits the string into two at the given byte index, like [`split_off`],
+    /// but returns an error instead of panicking, leaving the string untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `at` is not on a [`char`] boundary, or if it is beyond
+    /// the last code point of the string.
+    ///
+    /// [`split_off`]: struct.JavaString.html#method.split_off
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn checked_split_off(&mut self, at: usize) -> Result<JavaString, BoundsError> {
+        self.check_index(at)?;
+        let other = Self {
+            data: RawJavaString::from_bytes(&self.as_bytes()[at..]),
+        };
+        self.data = RawJavaString::from_bytes(&self.as_bytes()[0..at]);
+        Ok(other)
+    }
+
+    /// Returns the index of every occurrence of the byte `b` in this
+    /// `JavaString`, in order.
+    ///
+    /// With the `memchr` feature enabled, the search is vectorized.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("a\nb\n\nc");
+    ///
+    /// assert_eq!(s.byte_positions(b'\n'), [1, 3, 4]);
+    /// ```
+    pub fn byte_positions(&self, b: u8) -> Vec<usize> {
+        #[cfg(feature = "memchr")]
+        return memchr::memchr_iter(b, self.as_bytes()).collect();
+
+        #[cfg(not(feature = "memchr"))]
+        return self
+            .as_bytes()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &byte)| byte == b)
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    /// Returns an iterator over pieces of this `JavaString` that are each at
+    /// most `max_bytes` long. Every piece is cut on a character boundary, and
+    /// is as long as it can be without going over the budget.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if it reaches a character longer than `max_bytes`,
+    /// since no piece could hold it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("ab💖cd");
+    /// let pieces: Vec<_> = s.chunks_bytes(4).collect();
+    ///
+    /// assert_eq!(pieces, ["ab", "💖", "cd"]);
+    /// ```
+    pub fn chunks_bytes(&self, max_bytes: usize) -> impl Iterator<Item = JavaString> + '_ {
+        let mut rest = self.as_str();
+        core::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+
+            let (piece, tail) = rest.split_at(budget_prefix_len(rest, max_bytes));
+            rest = tail;
+            Some(JavaString::from(piece))
+        })
+    }
+
+    /// Removes and returns the longest prefix of this `JavaString` that's at
+    /// most `max_bytes` long and ends on a character boundary. Returns an
+    /// empty string if this one is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the first character is longer than `max_bytes`, since no
+    /// prefix could hold it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("ab💖cd");
+    ///
+    /// assert_eq!(s.split_off_prefix_bytes(5), "ab");
+    /// assert_eq!(s, "💖cd");
+    /// ```
+    pub fn split_off_prefix_bytes(&mut self, max_bytes: usize) -> JavaString {
+        let len = budget_prefix_len(self, max_bytes);
+        let rest = self.split_off(len);
+        core::mem::replace(self, rest)
+    }
+
+    /// Replaces every match of `from` with `to`, like `str::replace`, but
+    /// borrows this `JavaString`'s contents instead of allocating when there's
+    /// nothing to replace.
+    ///
+    /// An empty `from` matches between every character, just like it does for
+    /// `str::replace`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// use std::borrow::Cow;
+    ///
+    /// let s = JavaString::from("foo bar");
+    ///
+    /// assert_eq!(s.replace_cow("o", "0"), "f00 bar");
+    /// assert!(matches!(s.replace_cow("z", "0"), Cow::Borrowed("foo bar")));
+    /// ```
+    pub fn replace_cow<'a>(&'a self, from: &str, to: &str) -> alloc::borrow::Cow<'a, str> {
+        use alloc::borrow::Cow;
+
+        let text = self.as_str();
+        if from == to {
+            return Cow::Borrowed(text);
+        }
+
+        let mut matches = text.match_indices(from);
+        let first = match matches.next() {
+            Some((idx, _)) => idx,
+            None => return Cow::Borrowed(text),
+        };
+
+        let mut out = String::with_capacity(text.len());
+        out.push_str(&text[..first]);
+        out.push_str(to);
+        let mut last = first + from.len();
+        for (idx, _) in matches {
+            out.push_str(&text[last..idx]);
+            out.push_str(to);
+            last = idx + from.len();
+        }
+        out.push_str(&text[last..]);
+        Cow::Owned(out)
+    }
+
+    /// Returns this `JavaString`'s contents with leading and trailing
+    /// whitespace removed, like `str::trim`. Trimming never needs to allocate,
+    /// so this always borrows; it's here so that pipelines of `*_cow` methods
+    /// compose.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("  foo\n");
+    ///
+    /// assert_eq!(s.trim_cow(), "foo");
+    /// ```
+    pub fn trim_cow(&self) -> alloc::borrow::Cow<'_, str> {
+        alloc::borrow::Cow::Borrowed(self.as_str().trim())
+    }
+
+    /// Replaces every `\r\n` and lone `\r` with `\n`, borrowing this
+    /// `JavaString`'s contents instead of allocating when there's no `\r`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("a\r\nb\rc\n");
+    ///
+    /// assert_eq!(s.normalize_newlines_cow(), "a\nb\nc\n");
+    /// ```
+    pub fn normalize_newlines_cow(&self) -> alloc::borrow::Cow<'_, str> {
+        use alloc::borrow::Cow;
+
+        let text = self.as_str();
+        let first = match text.find('\r') {
+            Some(idx) => idx,
+            None => return Cow::Borrowed(text),
+        };
+
+        let mut out = String::with_capacity(text.len());
+        out.push_str(&text[..first]);
+        let mut rest = &text[first..];
+        while let Some(idx) = rest.find('\r') {
+            out.push_str(&rest[..idx]);
+            out.push('\n');
+            rest = &rest[idx + 1..];
+            rest = rest.strip_prefix('\n').unwrap_or(rest);
+        }
+        out.push_str(rest);
+        Cow::Owned(out)
+    }
+
+    /// Returns a guard giving mutable access to a copy of this `JavaString`'s
+    /// bytes, which are written back when the guard is dropped.
+    ///
+    /// Unlike `String::as_mut_vec`, this is safe: the guard checks that the
+    /// bytes are valid UTF-8 before writing them back, and panics if they
+    /// aren't.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("hello");
+    ///
+    /// {
+    ///     let mut bytes = s.as_mut_vec();
+    ///     bytes.reverse();
+    ///     bytes.push(b'!');
+    /// }
+    ///
+    /// assert_eq!(s, "olleh!");
+    /// ```
+    pub fn as_mut_vec(&mut self) -> MutVecGuard<'_> {
+        MutVecGuard::new(self)
+    }
+
+    /// Appends `ch` and returns the result, for chaining.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from(" foo\tbar ")
+    ///     .with_trimmed()
+    ///     .with_replaced("\t", " ")
+    ///     .with_pushed('\n');
+    ///
+    /// assert_eq!(s, "foo bar\n");
+    /// ```
+    pub fn with_pushed(mut self, ch: char) -> JavaString {
+        self.push(ch);
+        self
+    }
+
+    /// Appends `string` and returns the result, for chaining.
+    pub fn with_push_str(mut self, string: &str) -> JavaString {
+        self.push_str(string);
+        self
+    }
+
+    /// Replaces every match of `from` with `to` and returns the result, for
+    /// chaining. If nothing matches, `self` is returned as is. Otherwise the
+    /// result is written straight into a new string, which takes a single
+    /// allocation, or none if it's short enough to be interned.
+    pub fn with_replaced(self, from: &str, to: &str) -> JavaString {
+        let text = self.as_str();
+        let matches = text.match_indices(from).count();
+        if matches == 0 || from == to {
+            return self;
+        }
+
+        let new_len = text.len() - matches * from.len() + matches * to.len();
+        let mut data = RawJavaString::zeroed(new_len);
+        let out = data.get_bytes_mut();
+        let (mut read, mut write) = (0, 0);
+        for (idx, _) in text.match_indices(from) {
+            for piece in &[&text[read..idx], to] {
+                out[write..write + piece.len()].copy_from_slice(piece.as_bytes());
+                write += piece.len();
+            }
+            read = idx + from.len();
+        }
+        out[write..].copy_from_slice(&text.as_bytes()[read..]);
+
+        Self { data }
+    }
+
+    /// Removes leading and trailing whitespace and returns the result, for
+    /// chaining. If there's nothing to remove, `self` is returned as is.
+    pub fn with_trimmed(self) -> JavaString {
+        let trimmed = self.trim();
+        if trimmed.len() == self.len() {
+            return self;
+        }
+        JavaString::from(trimmed)
+    }
+
+    /// Shortens this `JavaString` to `new_len` bytes and returns the result,
+    /// for chaining. If `new_len` is at least the current length, `self` is
+    /// returned as is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    pub fn with_truncated(mut self, new_len: usize) -> JavaString {
+        if new_len < self.len() {
+            self.truncate(new_len);
+        }
+        self
+    }
+
+    /// Converts this `JavaString` to lowercase and returns the result, for
+    /// chaining. ASCII strings are converted in place, without allocating.
+    /// Other strings are encoded straight into a new string, which takes one
+    /// allocation at most, except that strings containing `'Σ'` go through
+    /// `str::to_lowercase` to get its word-final form right, which takes two.
+    pub fn with_lowercased(mut self) -> JavaString {
+        if self.is_ascii() {
+            self.make_ascii_lowercase();
+            return self;
+        }
+        if self.contains('Σ') {
+            return JavaString::from(&*self.to_lowercase());
+        }
+        JavaString::from_char_iter(self.chars().flat_map(char::to_lowercase))
+    }
+
+    /// Returns the length, in bytes, of the longest prefix this `JavaString`
+    /// shares with `other`. The length always lies on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("héllo");
+    ///
+    /// assert_eq!(s.common_prefix_len("hélp"), 4);
+    /// assert_eq!(s.common_prefix_len("hè"), 1);
+    /// assert_eq!(s.common_prefix_len("world"), 0);
+    /// ```
+    pub fn common_prefix_len(&self, other: &str) -> usize {
+        let mut len = self
+            .as_bytes()
+            .iter()
+            .zip(other.as_bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        // Equal bytes up to here means equal chars, so either both strings
+        // are on a boundary or neither is.
+        while !self.is_char_boundary(len) {
+            len -= 1;
+        }
+        len
+    }
+
+    /// Returns the longest prefix this `JavaString` shares with `other`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("héllo");
+    ///
+    /// assert_eq!(s.common_prefix("hélp"), "hél");
+    /// ```
+    pub fn common_prefix(&self, other: &str) -> &str {
+        &self[..self.common_prefix_len(other)]
+    }
+
+    /// Returns an iterator over the lines of this `JavaString`, like
+    /// `str::lines`, where each line comes with the byte offset it starts at.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("foo\r\nbar\nbaz\n");
+    /// let lines: Vec<_> = s.lines_with_offsets().collect();
+    ///
+    /// assert_eq!(lines, [(0, "foo"), (5, "bar"), (9, "baz")]);
+    /// ```
+    pub fn lines_with_offsets(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        let mut offset = 0;
+        self.split_inclusive('\n').map(move |line| {
+            let start = offset;
+            offset += line.len();
+
+            let line = match line.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => line,
+            };
+            (start, line)
+        })
+    }
+
+    /// Writes this `JavaString`'s contents into `w`, without going through
+    /// `Display`'s formatting machinery. `JavaString` is a `fmt::Write` sink
+    /// too, so this can append one `JavaString` to another.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut out = String::from("foo");
+    /// JavaString::from("bar").write_into(&mut out).unwrap();
+    ///
+    /// assert_eq!(out, "foobar");
+    /// ```
+    pub fn write_into<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        w.write_str(self.as_str())
+    }
+
+    /// Builds a `JavaString` from a slice of `char`s, encoding them straight
+    /// into the new string's buffer.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from_char_slice(&['h', 'é', '💖']);
+    ///
+    /// assert_eq!(s, "hé💖");
+    /// ```
+    pub fn from_char_slice(chars: &[char]) -> JavaString {
+        Self::from_char_iter(chars.iter().copied())
+    }
+
+    /// Returns a copy of this `JavaString`'s bytes in a new `Vec`.
+    pub fn to_owned_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Replaces the contents of `buf` with this `JavaString`'s bytes, reusing
+    /// `buf`'s capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut buf = b"old contents".to_vec();
+    /// JavaString::from("new").clone_into_vec(&mut buf);
+    ///
+    /// assert_eq!(buf, b"new");
+    /// ```
+    pub fn clone_into_vec(&self, buf: &mut Vec<u8>) {
+        buf.clear();
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    /// Splits this `JavaString` into two disjoint mutable string slices at the
+    /// byte index `mid`. Works the same whether the bytes are interned or on
+    /// the heap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is past the end of the string, or if it does not lie
+    /// on a [`char`] boundary.
+    ///
+    /// [`char`]: ../../std/primitive.char.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foobar");
+    ///
+    /// let (left, right) = s.split_at_mut(3);
+    /// left.make_ascii_uppercase();
+    /// right.make_ascii_uppercase();
+    ///
+    /// assert_eq!(s, "FOOBAR");
+    /// ```
+    #[track_caller]
+    pub fn split_at_mut(&mut self, mid: usize) -> (&mut str, &mut str) {
+        unwrap_bounds(self.check_index(mid));
+        self.as_mut_str().split_at_mut(mid)
+    }
+
+    /// Retains only the characters specified by the predicate.
+    ///
+    /// In other words, remove all characters `c` such that `f(c)` returns
+    /// `false`. This method operates in place, visiting each character exactly
+    /// once in the original order, and preserves the order of the retained
+    /// characters. The string is rebuilt at most once, and not at all if every
+    /// character is kept.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("f_o_ob_ar");
+    ///
+    /// s.retain(|c| c != '_');
+    ///
+    /// assert_eq!(s, "foobar");
+    /// ```
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut f: F) {
+        let mut kept = Vec::with_capacity(self.len());
+        for ch in self.chars() {
+            if f(ch) {
+                kept.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        if kept.len() != self.len() {
+            self.data = RawJavaString::from_bytes(&kept[..]);
+        }
+    }
+
+    /// Retains only the bytes that `f` returns `true` for, like [`retain`], but
+    /// without decoding characters. Only usable on ASCII strings, so removing
+    /// bytes can never break up a character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `JavaString` contains any non-ASCII bytes, before `f` is
+    /// ever called.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("user-name_42");
+    ///
+    /// s.retain_ascii(|b| b.is_ascii_alphanumeric());
+    /// assert_eq!(s, "username42");
+    /// ```
+    ///
+    /// [`retain`]: struct.JavaString.html#method.retain
+    pub fn retain_ascii<F: FnMut(u8) -> bool>(&mut self, mut f: F) {
+        assert!(
+            self.is_ascii(),
+            "retain_ascii called on a string with non-ASCII bytes"
+        );
+
+        let kept: Vec<u8> = self.as_bytes().iter().copied().filter(|&b| f(b)).collect();
+        if kept.len() != self.len() {
+            self.data = RawJavaString::from_bytes(&kept[..]);
+        }
+    }
+
+    /// Builds a `JavaString` of exactly `len` bytes, written by `fill`. Takes
+    /// one allocation, or none if the string is short enough to be interned.
+    ///
+    /// `fill` must initialize all `len` bytes; the buffer starts out zeroed, so
+    /// bytes it skips are left as `\0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the bytes written by `fill` aren't valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::with_exact(4, |buf| buf.copy_from_slice(b"abab")).unwrap();
+    ///
+    /// assert_eq!(s, "abab");
+    /// ```
+    pub fn with_exact<F>(len: usize, fill: F) -> Result<JavaString, alloc::string::FromUtf8Error>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut data = RawJavaString::zeroed(len);
+        fill(data.get_bytes_mut());
+
+        if core::str::from_utf8(data.get_bytes()).is_err() {
+            return Err(String::from_utf8(data.get_bytes().to_vec()).unwrap_err());
+        }
+        Ok(Self { data })
+    }
+
+    /// Appends another `JavaString` onto the end of this one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    ///
+    /// s.push_jstring(&JavaString::from("bar"));
+    /// assert_eq!(s, "foobar");
+    /// ```
+    pub fn push_jstring(&mut self, other: &JavaString) {
+        let sl: &[_] = &[self.as_bytes(), other.as_bytes()];
+        self.data = RawJavaString::from_bytes_array(sl);
+    }
+
+    /// Returns the first `char` of this `JavaString`, or `None` if it's empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// assert_eq!(JavaString::from("💖ab").first_char(), Some('💖'));
+    /// assert_eq!(JavaString::new().first_char(), None);
+    /// ```
+    pub fn first_char(&self) -> Option<char> {
+        self.chars().next()
+    }
+
+    /// Returns the last `char` of this `JavaString`, or `None` if it's empty.
+    /// Only the bytes of the last character are decoded.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// assert_eq!(JavaString::from("ab💖").last_char(), Some('💖'));
+    /// assert_eq!(JavaString::new().last_char(), None);
+    /// ```
+    pub fn last_char(&self) -> Option<char> {
+        self.chars().next_back()
+    }
+
+    /// Returns the `char` starting at byte index `idx`, or `default` if `idx`
+    /// is out of bounds or inside a character.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("a💖b");
+    ///
+    /// assert_eq!(s.char_at_or_default(1, '?'), '💖');
+    /// assert_eq!(s.char_at_or_default(2, '?'), '?');
+    /// assert_eq!(s.char_at_or_default(6, '?'), '?');
+    /// ```
+    pub fn char_at_or_default(&self, idx: usize, default: char) -> char {
+        self.get(idx..)
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or(default)
+    }
+
+    /// Returns this `JavaString`'s contents with every trailing `ch` removed.
+    /// Only the end of the string is scanned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("/usr/local///");
+    ///
+    /// assert_eq!(s.trim_end_matches_char('/'), "/usr/local");
+    /// ```
+    pub fn trim_end_matches_char(&self, ch: char) -> &str {
+        let mut text = self.as_str();
+        while let Some(rest) = text.strip_suffix(ch) {
+            text = rest;
+        }
+        text
+    }
+
+    /// Returns this `JavaString`'s contents with every leading `ch` removed.
+    /// Only the start of the string is scanned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("000120");
+    ///
+    /// assert_eq!(s.trim_start_matches_char('0'), "120");
+    /// ```
+    pub fn trim_start_matches_char(&self, ch: char) -> &str {
+        let mut text = self.as_str();
+        while let Some(rest) = text.strip_prefix(ch) {
+            text = rest;
+        }
+        text
+    }
+
+    /// Truncates this `JavaString`, removing all contents and freeing its
+    /// heap buffer, if it had one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("foo");
+    ///
+    /// s.clear();
+    /// assert!(s.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.data = RawJavaString::new();
+    }
+}
+
+/// Returns the length of the longest prefix of `text` that's at most
+/// `max_bytes` long and ends on a character boundary.
+///
+/// Panics if that prefix would be empty even though `text` isn't.
+fn budget_prefix_len(text: &str, max_bytes: usize) -> usize {
+    if text.len() <= max_bytes {
+        return text.len();
+    }
+
+    let mut len = max_bytes;
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    if len == 0 {
+        let width = text.chars().next().unwrap().len_utf8();
+        panic!(
+            "byte budget of {} is smaller than a {} byte character",
+            max_bytes, width
+        );
+    }
+    len
+}
+
+/// Unwraps the result of a `checked_*` method, panicking with the error's
+/// message on failure.
+#[inline(always)]
+#[track_caller]
+fn unwrap_bounds<T>(result: Result<T, BoundsError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+impl Default for JavaString {
+    /// Creates an empty `JavaString`, without allocating.
+    ///
+    /// This is what lets `#[derive(Default)]`, `core::mem::take`, and
+    /// `Option::unwrap_or_default` work with `JavaString`s.
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for JavaString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.as_str().fmt(formatter)
+    }
+}
+
+impl core::iter::FromIterator<char> for JavaString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let buf: String = iter.into_iter().collect();
+        Self::from(buf.as_str())
+    }
+}
+
+/// Builds a `JavaString` out of `pieces` with a single `from_bytes_array`
+/// call. Only the list of pieces is buffered, never their contents.
+fn concat_pieces<T: Deref<Target = [u8]>>(pieces: impl Iterator<Item = T>) -> JavaString {
+    let pieces: Vec<T> = pieces.collect();
+    JavaString {
+        data: RawJavaString::from_bytes_array(&pieces[..]),
+    }
+}
+
+/// Appends `pieces` to `string` with a single `from_bytes_array` call. If
+/// every piece is empty, `string` isn't rebuilt at all.
+fn push_pieces<T: Deref<Target = [u8]>>(string: &mut JavaString, pieces: impl Iterator<Item = T>) {
+    let pieces: Vec<T> = pieces.collect();
+    if pieces.iter().all(|piece| piece.is_empty()) {
+        return;
+    }
+
+    let mut bytes_array: Vec<&[u8]> = Vec::with_capacity(pieces.len() + 1);
+    bytes_array.push(string.as_bytes());
+    bytes_array.extend(pieces.iter().map(|piece| &**piece));
+    string.data = RawJavaString::from_bytes_array(&bytes_array[..]);
+}
+
+/// Converts a `Cow<str>` into its bytes, without copying.
+fn cow_bytes(piece: alloc::borrow::Cow<'_, str>) -> alloc::borrow::Cow<'_, [u8]> {
+    use alloc::borrow::Cow;
+    match piece {
+        Cow::Borrowed(piece) => Cow::Borrowed(piece.as_bytes()),
+        Cow::Owned(piece) => Cow::Owned(piece.into_bytes()),
+    }
+}
+
+impl<'a> core::iter::FromIterator<&'a str> for JavaString {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        concat_pieces(iter.into_iter().map(str::as_bytes))
+    }
+}
+
+impl core::iter::FromIterator<String> for JavaString {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        concat_pieces(iter.into_iter().map(String::into_bytes))
+    }
+}
+
+impl<'a> core::iter::FromIterator<alloc::borrow::Cow<'a, str>> for JavaString {
+    fn from_iter<I: IntoIterator<Item = alloc::borrow::Cow<'a, str>>>(iter: I) -> Self {
+        concat_pieces(iter.into_iter().map(cow_bytes))
+    }
+}
+
+impl core::iter::FromIterator<JavaString> for JavaString {
+    fn from_iter<I: IntoIterator<Item = JavaString>>(iter: I) -> Self {
+        concat_pieces(iter.into_iter().map(|string| string.data))
+    }
+}
+
+/// Gathers the new characters into a `String` first, so that the `JavaString`
+/// is rebuilt once rather than once per character. Extending with nothing
+/// leaves the string untouched.
+impl Extend<char> for JavaString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let mut buf = String::with_capacity(iter.size_hint().0);
+        buf.extend(iter);
+        if !buf.is_empty() {
+            self.push_str(&buf);
+        }
+    }
+}
+
+impl<'a> Extend<&'a str> for JavaString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        push_pieces(self, iter.into_iter().map(str::as_bytes));
+    }
+}
+
+impl Extend<String> for JavaString {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        push_pieces(self, iter.into_iter().map(String::into_bytes));
+    }
+}
+
+impl<'a> Extend<alloc::borrow::Cow<'a, str>> for JavaString {
+    fn extend<I: IntoIterator<Item = alloc::borrow::Cow<'a, str>>>(&mut self, iter: I) {
+        push_pieces(self, iter.into_iter().map(cow_bytes));
+    }
+}
+
+impl Extend<JavaString> for JavaString {
+    fn extend<I: IntoIterator<Item = JavaString>>(&mut self, iter: I) {
+        push_pieces(self, iter.into_iter().map(|string| string.data));
+    }
+}
+
+/// Implements the `+` operator for concatenating two strings.
+///
+/// This consumes the `JavaString` on the left-hand side, but since there's no
+/// spare capacity to grow into, its buffer can't be reused: each `+` copies
+/// both strings into a single new allocation, which is `O(n)`.
+///
+/// # Examples
+///
+/// ```
+/// # use jstring::JavaString;
+/// let a = JavaString::from("hello");
+/// let b = JavaString::from(" world");
+/// let c = a + &b + "!";
+///
+/// assert_eq!(c, "hello world!");
+/// ```
+impl<'a> core::ops::Add<&'a str> for JavaString {
+    type Output = JavaString;
+
+    #[inline]
+    fn add(mut self, rhs: &'a str) -> JavaString {
+        self.push_str(rhs);
+        self
+    }
+}
+
+impl<'a> core::ops::Add<&'a JavaString> for JavaString {
+    type Output = JavaString;
+
+    #[inline]
+    fn add(mut self, rhs: &'a JavaString) -> JavaString {
+        self.push_str(rhs);
+        self
+    }
+}
+
+/// Implements the `+=` operator for appending to a `JavaString`.
+///
+/// This has the same behavior as the [`push_str`] method, so it's also `O(n)`.
+///
+/// [`push_str`]: struct.JavaString.html#method.push_str
+impl<'a> core::ops::AddAssign<&'a str> for JavaString {
+    #[inline]
+    fn add_assign(&mut self, rhs: &'a str) {
+        self.push_str(rhs);
+    }
+}
+
+impl<'a> core::ops::AddAssign<&'a JavaString> for JavaString {
+    #[inline]
+    fn add_assign(&mut self, rhs: &'a JavaString) {
+        self.push_str(rhs);
+    }
+}
+
+impl core::ops::AddAssign<char> for JavaString {
+    #[inline]
+    fn add_assign(&mut self, rhs: char) {
+        self.push(rhs);
+    }
+}
+
+impl fmt::Write for JavaString {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.push_str(string);
+        Ok(())
+    }
+
+    fn write_char(&mut self, ch: char) -> fmt::Result {
+        self.push(ch);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for JavaString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.as_str().fmt(formatter)
+    }
+}
+
+impl Deref for JavaString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl DerefMut for JavaString {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl core::borrow::Borrow<str> for JavaString {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl core::borrow::BorrowMut<str> for JavaString {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl AsRef<str> for JavaString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsMut<str> for JavaString {
+    #[inline]
+    fn as_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl AsRef<[u8]> for JavaString {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<std::ffi::OsStr> for JavaString {
+    #[inline]
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+impl AsRef<std::path::Path> for JavaString {
+    #[inline]
+    fn as_ref(&self) -> &std::path::Path {
+        self.as_str().as_ref()
+    }
+}
+
+/// Implements `Index` and `IndexMut` for each range type `$range`, slicing the
+/// string's contents with the same panics as `str`.
+macro_rules! impl_index_range {
+    ($($range:ty),*) => {
+        $(
+            impl core::ops::Index<$range> for JavaString {
+                type Output = str;
+                #[inline]
+                fn index(&self, index: $range) -> &str {
+                    &self.as_str()[index]
+                }
+            }
+
+            impl core::ops::IndexMut<$range> for JavaString {
+                #[inline]
+                fn index_mut(&mut self, index: $range) -> &mut str {
+                    &mut self.as_mut_str()[index]
+                }
+            }
+        )*
+    };
+}
+
+impl_index_range!(
+    core::ops::RangeFull,
+    core::ops::Range<usize>,
+    core::ops::RangeFrom<usize>,
+    core::ops::RangeTo<usize>,
+    core::ops::RangeInclusive<usize>,
+    core::ops::RangeToInclusive<usize>
+);
+
+impl From<String> for JavaString {
+    fn from(string: String) -> Self {
+        Self {
+            data: RawJavaString::from_byte_vec(string.into_bytes()),
+        }
+    }
+}
+
+impl From<JavaString> for String {
+    #[inline]
+    fn from(string: JavaString) -> Self {
+        string.into_string()
+    }
+}
+
+impl From<char> for JavaString {
+    /// Encodes `ch` on the stack; a `char` always fits in an interned string,
+    /// so this never allocates.
+    #[inline]
+    fn from(ch: char) -> Self {
+        Self::from(&*ch.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<'a> From<&'a mut str> for JavaString {
+    #[inline]
+    fn from(string: &'a mut str) -> Self {
+        Self::from(&*string)
+    }
+}
+
+impl<'a> From<&'a String> for JavaString {
+    #[inline]
+    fn from(string: &'a String) -> Self {
+        Self::from(string.as_str())
+    }
+}
+
+impl From<JavaString> for alloc::borrow::Cow<'static, str> {
+    /// Copies the contents into an owned `String`, like [`into_string`].
+    ///
+    /// [`into_string`]: struct.JavaString.html#method.into_string
+    #[inline]
+    fn from(string: JavaString) -> Self {
+        alloc::borrow::Cow::Owned(string.into_string())
+    }
+}
+
+impl From<JavaString> for Vec<u8> {
+    /// Copies the contents into a new `Vec`, like [`into_bytes`].
+    ///
+    /// [`into_bytes`]: struct.JavaString.html#method.into_bytes
+    #[inline]
+    fn from(string: JavaString) -> Self {
+        string.into_bytes()
+    }
+}
+
+impl From<JavaString> for std::ffi::OsString {
+    #[inline]
+    fn from(string: JavaString) -> Self {
+        string.into_string().into()
+    }
+}
+
+impl From<JavaString> for std::path::PathBuf {
+    #[inline]
+    fn from(string: JavaString) -> Self {
+        string.into_string().into()
+    }
+}
+
+impl From<alloc::boxed::Box<str>> for JavaString {
+    fn from(string: alloc::boxed::Box<str>) -> Self {
+        Self::from(&*string)
+    }
+}
+
+impl<'a> From<&'a str> for JavaString {
+    fn from(string: &'a str) -> Self {
+        Self {
+            data: RawJavaString::from_bytes(string.as_bytes()),
+        }
+    }
+}
+
+impl<'a> From<alloc::borrow::Cow<'a, str>> for JavaString {
+    fn from(string: alloc::borrow::Cow<'a, str>) -> Self {
+        match string {
+            alloc::borrow::Cow::Borrowed(string) => Self::from(string),
+            alloc::borrow::Cow::Owned(string) => Self::from(string),
+        }
+    }
+}
+
+impl<'a> From<&'a JavaString> for alloc::borrow::Cow<'a, str> {
+    fn from(string: &'a JavaString) -> Self {
+        alloc::borrow::Cow::Borrowed(string.as_str())
+    }
+}
+
+impl core::str::FromStr for JavaString {
+    type Err = core::convert::Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl PartialOrd for JavaString {
+    fn partial_cmp(&self, rhs: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl PartialEq<str> for &JavaString {
+    fn eq(&self, rhs: &str) -> bool {
+        self.as_str().eq(rhs)
+    }
+}
+
+impl PartialEq<&JavaString> for str {
+    fn eq(&self, rhs: &&JavaString) -> bool {
+        self.eq(rhs.as_str())
+    }
+}
+
+impl PartialEq<String> for &JavaString {
+    fn eq(&self, rhs: &String) -> bool {
+        self.as_str().eq(rhs.as_str())
+    }
+}
+
+impl PartialEq<&JavaString> for String {
+    fn eq(&self, rhs: &&JavaString) -> bool {
+        self.as_str().eq(rhs.as_str())
+    }
+}
+
+impl<'a> PartialEq<&'a str> for JavaString {
+    fn eq(&self, rhs: &&'a str) -> bool {
+        self.as_str().eq(*rhs)
+    }
+}
+
+impl PartialEq<str> for JavaString {
+    fn eq(&self, rhs: &str) -> bool {
+        self.as_str().eq(rhs)
+    }
+}
+
+/// Implements string equality between `JavaString` and `$string`, in both
+/// orders.
+macro_rules! impl_eq_str {
+    ($string:ty $(, $lt:lifetime)?) => {
+        impl<$($lt)?> PartialEq<$string> for JavaString {
+            #[inline]
+            fn eq(&self, rhs: &$string) -> bool {
+                self.as_str() == &rhs[..]
+            }
+        }
+
+        impl<$($lt)?> PartialEq<JavaString> for $string {
+            #[inline]
+            fn eq(&self, rhs: &JavaString) -> bool {
+                &self[..] == rhs.as_str()
+            }
+        }
+    };
+}
+
+impl PartialEq<JavaString> for str {
+    #[inline]
+    fn eq(&self, rhs: &JavaString) -> bool {
+        self == rhs.as_str()
+    }
+}
+
+impl PartialEq<JavaString> for &str {
+    #[inline]
+    fn eq(&self, rhs: &JavaString) -> bool {
+        *self == rhs.as_str()
+    }
+}
+
+impl_eq_str!(String);
+impl_eq_str!(alloc::borrow::Cow<'a, str>, 'a);
+
+/// Implements byte equality between `JavaString` and `$bytes`, in both
+/// orders.
+macro_rules! impl_eq_bytes {
+    ($bytes:ty $(, const $n:ident)?) => {
+        impl<$(const $n: usize)?> PartialEq<$bytes> for JavaString {
+            fn eq(&self, rhs: &$bytes) -> bool {
+                self.as_bytes() == &rhs[..]
+            }
+        }
+
+        impl<$(const $n: usize)?> PartialEq<JavaString> for $bytes {
+            fn eq(&self, rhs: &JavaString) -> bool {
+                &self[..] == rhs.as_bytes()
+            }
+        }
+    };
+}
+
+// These compare bytes, so they can be true for a `[u8]` whether or not it's
+// valid UTF-8, as long as it's the same bytes as the `JavaString`.
+impl_eq_bytes!([u8]);
+impl_eq_bytes!(&[u8]);
+impl_eq_bytes!(Vec<u8>);
+impl_eq_bytes!([u8; N], const N);
+impl_eq_bytes!(&[u8; N], const N);
+
+/// Implements ordering between `JavaString` and `$string`, in both orders, by
+/// comparing their contents as `str`s, which is consistent with `Ord`.
+macro_rules! impl_ord_str {
+    ($string:ty) => {
+        impl PartialOrd<$string> for JavaString {
+            #[inline]
+            fn partial_cmp(&self, rhs: &$string) -> Option<core::cmp::Ordering> {
+                self.as_str().partial_cmp(&rhs[..])
+            }
+        }
+
+        impl PartialOrd<JavaString> for $string {
+            #[inline]
+            fn partial_cmp(&self, rhs: &JavaString) -> Option<core::cmp::Ordering> {
+                self[..].partial_cmp(rhs.as_str())
+            }
+        }
+    };
+}
+
+impl_ord_str!(str);
+impl_ord_str!(&str);
+impl_ord_str!(String);
+
+impl Ord for JavaString {
+    fn cmp(&self, rhs: &Self) -> core::cmp::Ordering {
+        self.as_str().cmp(rhs)
+    }
+}
+
+impl core::hash::Hash for JavaString {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl serde::Serialize for JavaString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for JavaString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self {
+            data: RawJavaString::from_byte_vec(String::deserialize(deserializer)?.into_bytes()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Small xorshift generator, so that tests don't need an extra dependency.
+    pub(crate) struct XorShift(pub(crate) u64);
+
+    impl XorShift {
+        pub(crate) fn next(&mut self) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as usize
+        }
+    }
+
+    #[test]
+    fn shrink_to_fit_interns_short_strings() {
+        let chars = ['a', 'z', 'é', '€', '💖'];
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        let mut string = JavaString::new();
+        let mut max_len = 0;
+
+        for _ in 0..10_000 {
+            match rng.next() % 6 {
+                0..=2 => string.push(chars[rng.next() % chars.len()]),
+                3 => {
+                    string.pop();
+                }
+                4 => {
+                    if let Some((idx, _)) = string.char_indices().nth(rng.next() % 4) {
+                        string.remove(idx);
+                    }
+                }
+                _ => {
+                    let boundaries: Vec<usize> = string.char_indices().map(|(i, _)| i).collect();
+                    if rng.next() % 8 == 0 || boundaries.is_empty() {
+                        string.truncate(0);
+                    } else {
+                        string.truncate(boundaries[rng.next() % boundaries.len()]);
+                    }
+                }
+            }
+
+            string.shrink_to_fit();
+            assert_eq!(
+                string.data.is_interned(),
+                string.len() <= RawJavaString::max_intern_len(),
+                "String of length {} has the wrong representation",
+                string.len()
+            );
+            max_len = max_len.max(string.len());
+        }
+
+        assert!(max_len > RawJavaString::max_intern_len());
+    }
+
+    #[test]
+    fn replace_char_changing_width() {
+        let mut string = JavaString::from("añb€c");
+
+        assert_eq!(string.replace_char(0, '💖'), Some('a'));
+        assert_eq!(string, "💖ñb€c");
+
+        assert_eq!(string.replace_char(3, 'e'), Some('€'));
+        assert_eq!(string, "💖ñbec");
+
+        assert_eq!(string.replace_char(1, 'ü'), Some('ñ'));
+        assert_eq!(string, "💖übec");
+
+        assert_eq!(string.replace_char(5, 'x'), None);
+        assert_eq!(string, "💖übec");
+    }
+
+    #[test]
+    fn replace_char_heap() {
+        let mut string = JavaString::from("a string that lives on the heap");
+
+        assert_eq!(string.replace_char(30, '💖'), Some('p'));
+        assert_eq!(string, "a string that lives on the hea💖");
+        assert!(!string.data.is_interned());
+    }
+
+    #[test]
+    fn from_utf8_mut_takes_contents() {
+        let mut bytes = "a string that lives on the heap".as_bytes().to_vec();
+        let before = live_bytes();
+
+        // The contents are copied into a new buffer, and the old one is freed.
+        let (string, allocations) = count_allocations(|| JavaString::from_utf8_mut(&mut bytes));
+        let string = string.unwrap();
+        assert_eq!(allocations, 1);
+        assert_eq!(live_bytes(), before);
+        assert_eq!(string, "a string that lives on the heap");
+        assert!(!string.data.is_interned());
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn from_utf8_mut_rejects_invalid() {
+        let mut bytes = b"caf\xc3".to_vec();
+
+        let err = JavaString::from_utf8_mut(&mut bytes).unwrap_err();
+        assert_eq!(err.valid_up_to(), 3);
+        assert_eq!(bytes, b"caf\xc3");
+
+        bytes.push(0xa9);
+        let string = JavaString::from_utf8_mut(&mut bytes).unwrap();
+        assert_eq!(string, "café");
+        assert!(string.data.is_interned());
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn bytes_eq() {
+        let short = JavaString::from("token");
+        assert!(short.bytes_eq(b"token"));
+        assert!(!short.bytes_eq(b"toke"));
+        assert!(!short.bytes_eq(b"tokens"));
+        assert!(!short.bytes_eq(b"toke\xff"));
+
+        let long = JavaString::from("a token that lives on the heap");
+        assert!(long.bytes_eq(b"a token that lives on the heap"));
+        assert!(!long.bytes_eq(b"a token that lives on the hea"));
+        assert!(!long.bytes_eq(b"a token that lives on the heap!"));
+    }
+
+    #[test]
+    fn prepend() {
+        let mut string = JavaString::from("bar");
+        string.prepend("foo");
+        string.prepend_char('/');
+        assert_eq!(string, "/foobar");
+        assert!(string.data.is_interned());
+
+        let mut string = JavaString::from("a string that lives on the heap");
+        string.prepend_char('💖');
+        assert_eq!(string, "💖a string that lives on the heap");
+    }
+
+    #[test]
+    fn prepend_spills_to_heap() {
+        let mut string = JavaString::from("0123456789");
+        string.prepend("abcdef");
+        assert_eq!(string, "abcdef0123456789");
+        assert!(!string.data.is_interned());
+    }
+
+    #[test]
+    fn count_and_last_line() {
+        let cases = [
+            "",
+            "\n",
+            "one line",
+            "one line\n",
+            "first\nsecond",
+            "first\nsecond\n",
+            "first\r\nsecond\r\n",
+            "first\n\nthird\n\n",
+            "a string that lives on the heap\nand has two lines",
+            "trailing\r",
+        ];
+
+        for case in cases.iter() {
+            let string = JavaString::from(*case);
+            assert_eq!(string.count_lines(), case.lines().count(), "{:?}", case);
+            assert_eq!(
+                string.last_line(),
+                case.lines().last().unwrap_or(""),
+                "{:?}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_to_byte_boundary() {
+        let mut string = JavaString::from("héllo 💖 this string lives on the heap");
+        string.truncate_to_byte_boundary(100);
+        assert_eq!(string, "héllo 💖 this string lives on the heap");
+
+        for max_bytes in 8..=10 {
+            let mut string = string.clone();
+            string.truncate_to_byte_boundary(max_bytes);
+            assert_eq!(string, "héllo ");
+        }
+
+        string.truncate_to_byte_boundary(11);
+        assert_eq!(string, "héllo 💖");
+
+        string.truncate_to_byte_boundary(2);
+        assert_eq!(string, "h");
+
+        string.truncate_to_byte_boundary(0);
+        assert_eq!(string, "");
+    }
+
+    pub(crate) fn hash_of<T: core::hash::Hash + ?Sized>(value: &T) -> u64 {
+        use core::hash::{BuildHasher, BuildHasherDefault};
+        BuildHasherDefault::<std::collections::hash_map::DefaultHasher>::default().hash_one(value)
+    }
+
+    #[test]
+    fn hash_matches_str() {
+        for text in &[
+            "",
+            "short",
+            "exactly 15 byte",
+            "a string that lives on the heap",
+        ] {
+            let string = JavaString::from(*text);
+            assert_eq!(hash_of(&string), hash_of(*text));
+            assert_eq!(hash_of(&string), hash_of(&String::from(*text)));
+
+            // Building a string up piece by piece doesn't change its hash.
+            let mut built = JavaString::new();
+            built.extend(text.chars());
+            assert_eq!(hash_of(&built), hash_of(&string));
+        }
+    }
+
+    thread_local! {
+        static ALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+        static LIVE_BYTES: core::cell::Cell<isize> = const { core::cell::Cell::new(0) };
+        static ODD_BYTE_BUFFERS: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+    }
+
+    /// Runs `f` with every byte-aligned allocation made by this thread placed
+    /// at an odd address, like a `Vec<u8>`'s buffer is allowed to be.
+    pub(crate) fn with_odd_byte_buffers<T>(f: impl FnOnce() -> T) -> T {
+        ODD_BYTE_BUFFERS.with(|odd| odd.set(true));
+        let result = f();
+        ODD_BYTE_BUFFERS.with(|odd| odd.set(false));
+        result
+    }
+
+    /// Byte-aligned allocations get an extra byte and an alignment of 2, so
+    /// they can be placed at either parity and still be freed from the even
+    /// address below them. Miri can't see that the padding byte belongs to the
+    /// buffer, so it gets plain allocations.
+    fn is_padded(layout: std::alloc::Layout) -> bool {
+        layout.align() == 1 && !cfg!(miri)
+    }
+
+    fn padded(layout: std::alloc::Layout) -> std::alloc::Layout {
+        std::alloc::Layout::from_size_align(layout.size() + 1, 2).unwrap()
+    }
+
+    fn base(ptr: *mut u8) -> *mut u8 {
+        ptr.wrapping_sub(ptr as usize % 2)
+    }
+
+    /// Allocator that counts the allocations made by each thread, so tests can
+    /// check how often an operation allocates.
+    struct CountingAllocator;
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + layout.size() as isize));
+            if !is_padded(layout) {
+                return std::alloc::System.alloc(layout);
+            }
+
+            let ptr = std::alloc::System.alloc(padded(layout));
+            let odd = ODD_BYTE_BUFFERS.try_with(|odd| odd.get()).unwrap_or(false);
+            if odd && !ptr.is_null() {
+                ptr.add(1)
+            } else {
+                ptr
+            }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            let _ = LIVE_BYTES.try_with(|live| live.set(live.get() - layout.size() as isize));
+            if !is_padded(layout) {
+                return std::alloc::System.dealloc(ptr, layout);
+            }
+            std::alloc::System.dealloc(base(ptr), padded(layout))
+        }
+
+        unsafe fn realloc(
+            &self,
+            ptr: *mut u8,
+            layout: std::alloc::Layout,
+            new_size: usize,
+        ) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            let grown = new_size as isize - layout.size() as isize;
+            let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + grown));
+            if !is_padded(layout) {
+                return std::alloc::System.realloc(ptr, layout, new_size);
+            }
+
+            // Keep the buffer at the same parity, so it's still freed correctly.
+            let offset = ptr as usize % 2;
+            let new = std::alloc::System.realloc(base(ptr), padded(layout), new_size + 1);
+            if new.is_null() {
+                new
+            } else {
+                new.add(offset)
+            }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    /// Runs `f`, and returns its result along with the number of allocations
+    /// and reallocations it made.
+    pub(crate) fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(|count| count.get());
+        let result = f();
+        (result, ALLOCATIONS.with(|count| count.get()) - before)
+    }
+
+    /// Returns the number of bytes allocated by this thread that haven't been
+    /// freed yet.
+    pub(crate) fn live_bytes() -> isize {
+        LIVE_BYTES.with(|live| live.get())
+    }
+
+    #[test]
+    fn make_mut() {
+        let mut string = JavaString::from("a string that lives on the heap");
+        let ((), allocations) = count_allocations(|| string.make_mut().make_ascii_uppercase());
+        assert_eq!(allocations, 0);
+        assert_eq!(string, "A STRING THAT LIVES ON THE HEAP");
+
+        let first = string.clone();
+        let second = string.clone();
+        string.make_mut().make_ascii_lowercase();
+        assert_eq!(string, "a string that lives on the heap");
+        assert_eq!(first, "A STRING THAT LIVES ON THE HEAP");
+        assert_eq!(second, "A STRING THAT LIVES ON THE HEAP");
+    }
+
+    #[test]
+    fn from_str_repeat_capped() {
+        let string = JavaString::from_str_repeat_capped("💖ab", 10, 20);
+        assert_eq!(string, "💖ab💖ab💖ab");
+
+        let string = JavaString::from_str_repeat_capped("💖ab", 10, 5);
+        assert_eq!(string, "");
+
+        let string = JavaString::from_str_repeat_capped("💖ab", 4, 100);
+        assert_eq!(string, "💖ab💖ab💖ab💖ab");
+        assert!(!string.data.is_interned());
+
+        let string = JavaString::from_str_repeat_capped("", 4, 0);
+        assert_eq!(string, "");
+
+        let (string, allocations) =
+            count_allocations(|| JavaString::from_str_repeat_capped("", usize::MAX, 10));
+        assert_eq!(string, "");
+        assert_eq!(allocations, 0);
+
+        let (string, allocations) =
+            count_allocations(|| JavaString::from_str_repeat_capped("ab", usize::MAX, 40));
+        assert_eq!(string, "ab".repeat(20).as_str());
+        assert_eq!(allocations, 1);
+    }
+
+    #[test]
+    fn default_derives() {
+        #[derive(Default)]
+        struct Wrapper {
+            name: JavaString,
+            alias: Option<JavaString>,
+        }
+
+        let ((), allocations) = count_allocations(|| {
+            let wrapper = Wrapper::default();
+            assert_eq!(wrapper.name, "");
+            assert!(wrapper.name.data.is_interned());
+            assert_eq!(wrapper.alias.unwrap_or_default(), "");
+        });
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn take_and_replace() {
+        for text in &["short", "a string that lives on the heap"] {
+            let mut string = JavaString::from(*text);
+            let before = live_bytes();
+
+            let taken = core::mem::take(&mut string);
+            assert_eq!(taken, *text);
+            assert_eq!(string, "");
+            assert!(string.data.is_interned());
+            assert_eq!(live_bytes(), before);
+
+            string = taken;
+            let taken = string.take();
+            assert_eq!(taken, *text);
+            assert_eq!(string, "");
+            assert_eq!(live_bytes(), before);
+
+            let old = string.replace_contents(taken);
+            assert_eq!(old, "");
+            assert_eq!(string, *text);
+            assert_eq!(live_bytes(), before);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "debug_checks", debug_assertions))]
+    #[should_panic(expected = "JavaString contains invalid UTF-8")]
+    fn debug_checks_catch_invalid_utf8() {
+        let mut string = JavaString::from("abc");
+        unsafe {
+            string.as_bytes_mut()[1] = 0xff;
+        }
+        let _ = string.as_str();
+    }
+
+    #[test]
+    fn checked_methods_reject_bad_indices() {
+        use BoundsError::*;
+
+        for text in &["hé💖", "hé💖, this string lives on the heap"] {
+            let mut string = JavaString::from(*text);
+            let len = string.len();
+
+            let (result, allocations) = count_allocations(|| {
+                [
+                    string.checked_remove(len).map(drop),
+                    string.checked_remove(2).map(drop),
+                    string.checked_insert(len + 1, 'x'),
+                    string.checked_insert(2, 'x'),
+                    string.checked_insert_str(len + 1, "xy"),
+                    string.checked_insert_str(4, "xy"),
+                    string.checked_replace_range(1..len + 1, "xy"),
+                    string.checked_replace_range(2..3, "xy"),
+                    string.checked_replace_range(1..=3, "xy"),
+                    string.checked_replace_range((Bound::Included(3), Bound::Excluded(1)), "xy"),
+                    string.checked_truncate(len + 1),
+                    string.checked_truncate(5),
+                    string.checked_split_off(len + 1).map(drop),
+                    string.checked_split_off(2).map(drop),
+                ]
+            });
+
+            let out_of_bounds = |index| Err(OutOfBounds { index, len });
+            let not_boundary = |index| Err(NotCharBoundary { index });
+            assert_eq!(
+                result,
+                [
+                    out_of_bounds(len),
+                    not_boundary(2),
+                    out_of_bounds(len + 1),
+                    not_boundary(2),
+                    out_of_bounds(len + 1),
+                    not_boundary(4),
+                    out_of_bounds(len + 1),
+                    not_boundary(2),
+                    not_boundary(4),
+                    Err(InvalidRange { start: 3, end: 1 }),
+                    Ok(()),
+                    not_boundary(5),
+                    out_of_bounds(len + 1),
+                    not_boundary(2),
+                ]
+            );
+            assert_eq!(allocations, 0);
+            assert_eq!(string, *text);
+        }
+    }
+
+    #[test]
+    fn checked_methods_match_panicking() {
+        let text = "hé💖, this string lives on the heap";
+        let (mut checked, mut panicking) = (JavaString::from(text), JavaString::from(text));
+
+        assert_eq!(checked.checked_remove(1), Ok(panicking.remove(1)));
+        checked.checked_insert(1, '€').unwrap();
+        panicking.insert(1, '€');
+        checked.checked_insert_str(0, "💖").unwrap();
+        panicking.insert_str(0, "💖");
+        checked.checked_replace_range(5..=7, "e").unwrap();
+        panicking.replace_range(5..=7, "e");
+        assert_eq!(checked, panicking);
+        assert_eq!(checked, "💖he💖, this string lives on the heap");
+
+        assert_eq!(checked.checked_split_off(10), Ok(panicking.split_off(10)));
+        checked.checked_truncate(4).unwrap();
+        panicking.truncate(4);
+        assert_eq!(checked, panicking);
+        assert_eq!(checked, "💖");
+    }
+
+    #[test]
+    fn insert_char() {
+        let mut string = JavaString::from("é€");
+        string.insert(0, 'a');
+        string.insert(3, 'b');
+        string.insert(string.len(), 'c');
+        assert_eq!(string, "aéb€c");
+        string.insert(1, '💖');
+        assert_eq!(string, "a💖éb€c");
+
+        let max = RawJavaString::max_intern_len();
+        let mut string = JavaString::from("x".repeat(max));
+        assert!(string.stats().is_interned);
+        string.insert(max / 2, 'é');
+        assert!(!string.stats().is_interned);
+        let mut expected = "x".repeat(max);
+        expected.insert(max / 2, 'é');
+        assert_eq!(string, expected.as_str());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn insert_panics_past_end() {
+        JavaString::from("hé").insert(4, 'x');
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 2 is not a char boundary")]
+    fn insert_panics_off_boundary() {
+        JavaString::from("hé").insert(2, 'x');
+    }
+
+    #[test]
+    fn concat_pair() {
+        let max = RawJavaString::max_intern_len();
+        let left = "a".repeat(max / 2);
+        let right = "b".repeat(max - max / 2);
+
+        let (string, allocations) = count_allocations(|| JavaString::concat_pair(&left, &right));
+        assert!(string.data.is_interned());
+        assert_eq!(allocations, 0);
+        assert_eq!(string, *format!("{}{}", left, right));
+
+        let right = format!("{}b", right);
+        let (string, allocations) = count_allocations(|| JavaString::concat_pair(&left, &right));
+        assert!(!string.data.is_interned());
+        assert_eq!(allocations, 1);
+        assert_eq!(string, *format!("{}{}", left, right));
+
+        assert_eq!(JavaString::concat_pair("", ""), "");
+        assert_eq!(JavaString::concat_pair("💖", ""), "💖");
+    }
+
+    #[test]
+    fn stats() {
+        let max = RawJavaString::max_intern_len();
+
+        let short = JavaString::from(&*"a".repeat(max));
+        assert_eq!(
+            short.stats(),
+            JavaStringStats {
+                len: max,
+                is_interned: true,
+                allocated_bytes: 0,
+            }
+        );
+
+        let long = JavaString::from(&*"a".repeat(max + 1));
+        assert_eq!(
+            long.stats(),
+            JavaStringStats {
+                len: max + 1,
+                is_interned: false,
+                allocated_bytes: max + 1,
+            }
+        );
+    }
+
+    #[test]
+    fn byte_positions() {
+        let string = JavaString::from("line one\nline two\r\n\nline 💖\n");
+        assert_eq!(string.byte_positions(b'\n'), [8, 18, 19, 29]);
+        assert_eq!(string.byte_positions(b'\r'), [17]);
+        assert_eq!(string.byte_positions(0xf0), [25]);
+
+        let string = JavaString::from("no newlines here");
+        assert!(string.byte_positions(b'\n').is_empty());
+        assert!(JavaString::new().byte_positions(b'\n').is_empty());
+    }
+
+    #[test]
+    fn chunks_bytes() {
+        let text = "ab💖cd💖💖e, and some more text so this lives on the heap";
+        let string = JavaString::from(text);
+
+        for max_bytes in 4..=text.len() + 1 {
+            let pieces: Vec<_> = string.chunks_bytes(max_bytes).collect();
+            assert!(pieces.iter().all(|piece| piece.len() <= max_bytes));
+            assert_eq!(
+                pieces.iter().map(JavaString::as_str).collect::<String>(),
+                text
+            );
+
+            let mut drained = string.clone();
+            for piece in &pieces {
+                assert_eq!(drained.split_off_prefix_bytes(max_bytes), *piece);
+            }
+            assert_eq!(drained, "");
+            assert_eq!(drained.split_off_prefix_bytes(max_bytes), "");
+        }
+
+        // A 💖 straddles every budget boundary here.
+        let pieces: Vec<_> = string.chunks_bytes(5).take(4).collect();
+        assert_eq!(pieces, ["ab", "💖c", "d💖", "💖e"]);
+
+        // Pieces that fit exactly.
+        let pieces: Vec<_> = JavaString::from("💖💖💖").chunks_bytes(4).collect();
+        assert_eq!(pieces, ["💖", "💖", "💖"]);
+        let pieces: Vec<_> = JavaString::from("💖💖💖").chunks_bytes(8).collect();
+        assert_eq!(pieces, ["💖💖", "💖"]);
+
+        assert_eq!(JavaString::new().chunks_bytes(0).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "byte budget of 3 is smaller than a 4 byte character")]
+    fn chunks_bytes_budget_too_small() {
+        JavaString::from("ab💖").chunks_bytes(3).for_each(drop);
+    }
+
+    #[test]
+    #[should_panic(expected = "byte budget of 0 is smaller than a 1 byte character")]
+    fn split_off_prefix_bytes_budget_too_small() {
+        JavaString::from("ab").split_off_prefix_bytes(0);
+    }
+
+    #[test]
+    fn cow_methods() {
+        use alloc::borrow::Cow;
+
+        for text in &["clean", "clean, and long enough for the heap"] {
+            let string = JavaString::from(*text);
+            for cow in &[
+                string.replace_cow("dirty", "x"),
+                string.replace_cow("", ""),
+                string.replace_cow("clean", "clean"),
+                string.normalize_newlines_cow(),
+            ] {
+                match cow {
+                    Cow::Borrowed(borrowed) => assert_eq!(borrowed.as_ptr(), string.as_ptr()),
+                    Cow::Owned(_) => panic!("expected {:?} to be borrowed", cow),
+                }
+            }
+            assert_eq!(string.trim_cow().as_ptr(), string.as_ptr());
+        }
+
+        let string = JavaString::from("a💖b💖💖c");
+        assert_eq!(string.replace_cow("💖", "--"), "a--b----c");
+        assert_eq!(string.replace_cow("💖", ""), "abc");
+        assert_eq!(string.replace_cow("a", "💖"), "💖💖b💖💖c");
+        assert_eq!(string.replace_cow("", "|"), "|a|💖|b|💖|💖|c|");
+        assert_eq!(JavaString::new().replace_cow("", "|"), "|");
+
+        let string = JavaString::from("\r\r\na\rb\r\n\n\r");
+        assert_eq!(string.normalize_newlines_cow(), "\n\na\nb\n\n\n");
+
+        let string = JavaString::from(" \t trimmed 💖\n");
+        match string.trim_cow() {
+            Cow::Borrowed(trimmed) => assert_eq!(trimmed, "trimmed 💖"),
+            Cow::Owned(_) => panic!("trim_cow allocated"),
+        }
+    }
+
+    #[test]
+    fn cow_methods_skip_clean_inputs() {
+        let corpus: Vec<JavaString> = (0..100)
+            .map(|i| {
+                if i % 20 == 0 {
+                    JavaString::from(format!("input {} has a bad\r\nword", i))
+                } else {
+                    JavaString::from(format!("input {} is clean", i))
+                }
+            })
+            .collect();
+
+        let (owned, allocations) = count_allocations(|| {
+            corpus
+                .iter()
+                .map(|input| {
+                    let replaced = input.replace_cow("bad", "ok");
+                    let normalized = input.normalize_newlines_cow();
+                    matches!(replaced, alloc::borrow::Cow::Owned(_)) as usize
+                        + matches!(normalized, alloc::borrow::Cow::Owned(_)) as usize
+                })
+                .sum::<usize>()
+        });
+
+        assert_eq!(owned, 10);
+        assert_eq!(allocations, 10);
+    }
+
+    #[test]
+    fn from_utf8_replace() {
+        let bytes = [
+            &b"ok \xff\xfe then "[..],
+            "💖".as_bytes(),
+            b" and \xf0\x9f\x92",
+        ]
+        .concat();
+        assert_eq!(
+            JavaString::from_utf8_replace(&bytes, '?'),
+            "ok ?? then 💖 and ?"
+        );
+        assert_eq!(
+            JavaString::from_utf8_replace(&bytes, '💔'),
+            "ok 💔💔 then 💖 and 💔"
+        );
+        assert_eq!(
+            JavaString::from_utf8_replace(&bytes, '\u{FFFD}'),
+            *String::from_utf8_lossy(&bytes)
+        );
+        assert_eq!(JavaString::from_utf8_replace(b"clean", '?'), "clean");
+        assert_eq!(JavaString::from_utf8_replace(b"", '?'), "");
+    }
+
+    #[test]
+    fn eq_bytes() {
+        for text in &["GET", "a string that lives on the heap"] {
+            let string = JavaString::from(*text);
+            let bytes = text.as_bytes();
+
+            assert_eq!(string, *bytes);
+            assert_eq!(*bytes, string);
+            assert_eq!(string, bytes);
+            assert_eq!(bytes, string);
+            assert_eq!(string, bytes.to_vec());
+            assert_eq!(bytes.to_vec(), string);
+            assert_eq!(string.data, *bytes);
+            assert_eq!(*bytes, string.data);
+            assert_eq!(string.data, bytes);
+            assert_eq!(bytes, string.data);
+            assert_eq!(string.data, bytes.to_vec());
+            assert_eq!(bytes.to_vec(), string.data);
+        }
+
+        let string = JavaString::from("GET");
+        assert_eq!(string, b"GET");
+        assert_eq!(b"GET", string);
+        assert_eq!(string, *b"GET");
+        assert_eq!(*b"GET", string);
+        assert_ne!(string, b"PUT");
+        assert_ne!(b"PUT", string);
+
+        // Invalid UTF-8 never equals any JavaString's bytes.
+        for invalid in &[&b"GE\xff"[..], b"\xff", b"\xf0\x9f\x92"] {
+            for text in &["GE", "GE\u{ff}", "\u{fffd}", "💖", ""] {
+                let string = JavaString::from(*text);
+                assert_ne!(string, *invalid);
+                assert_ne!(*invalid, string);
+                assert_ne!(string.data, *invalid);
+            }
+        }
+    }
+
+    #[test]
+    fn as_mut_vec() {
+        let mut string = JavaString::from("a string that starts out on the heap");
+        assert!(!string.data.is_interned());
+
+        string.as_mut_vec().truncate(8);
+        assert_eq!(string, "a string");
+        assert!(string.data.is_interned());
+
+        {
+            let mut bytes = string.as_mut_vec();
+            bytes.make_ascii_uppercase();
+            bytes.extend_from_slice(" 💖 that ends up on the heap".as_bytes());
+        }
+        assert_eq!(string, "A STRING 💖 that ends up on the heap");
+        assert!(!string.data.is_interned());
+    }
+
+    #[test]
+    fn as_mut_vec_invalid_utf8() {
+        let mut string = JavaString::from("💖");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            string.as_mut_vec().pop();
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(string, "💖");
+    }
+
+    #[test]
+    fn chaining_combinators() {
+        let raw = "  Some\tMIXED Case text that lives on the heap \n";
+
+        let chained = JavaString::from(raw)
+            .with_trimmed()
+            .with_replaced("\t", " ")
+            .with_lowercased()
+            .with_truncated(20)
+            .with_push_str(" and ")
+            .with_pushed('💖');
+
+        let mut mutated = JavaString::from(raw.trim());
+        mutated = JavaString::from(&*mutated.replace('\t', " "));
+        mutated.make_ascii_lowercase();
+        mutated.truncate(20);
+        mutated.push_str(" and ");
+        mutated.push('💖');
+
+        assert_eq!(chained, mutated);
+        assert_eq!(chained, "some mixed case text and 💖");
+        assert_eq!(JavaString::from("ÀB").with_lowercased(), "àb");
+    }
+
+    #[test]
+    fn chaining_combinators_reuse_allocations() {
+        // Nothing to change, so the heap buffer passes straight through.
+        let heap = JavaString::from("clean text that lives on the heap");
+        let ptr = heap.as_ptr();
+        let (heap, allocations) = count_allocations(|| {
+            heap.with_trimmed()
+                .with_replaced("\t", " ")
+                .with_lowercased()
+                .with_truncated(100)
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(heap.as_ptr(), ptr);
+
+        // Shrinking a heap string into an interned one doesn't allocate.
+        let (short, allocations) =
+            count_allocations(|| heap.with_truncated(11).with_trimmed().with_lowercased());
+        assert_eq!(allocations, 0);
+        assert_eq!(short, "clean text");
+
+        // Interned strings never touch the heap.
+        let (short, allocations) = count_allocations(|| {
+            JavaString::from(" AB\tC ")
+                .with_trimmed()
+                .with_replaced("\t", " ")
+                .with_lowercased()
+                .with_pushed('!')
+                .with_push_str("?")
+                .with_truncated(5)
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(short, "ab c!");
+
+        // Replacing on the heap writes the result straight into its buffer.
+        let heap = JavaString::from("tabbed\ttext that lives on the heap");
+        let (heap, allocations) = count_allocations(|| heap.with_replaced("\t", "    "));
+        assert_eq!(allocations, 1);
+        assert_eq!(heap, "tabbed    text that lives on the heap");
+        let expected = heap.as_str().replace("", "|");
+        assert_eq!(heap.with_replaced("", "|"), *expected);
+
+        // Non-ASCII text is lowercased straight into the new string.
+        let loud = JavaString::from("ÉCOLE İSTANBUL, ON THE HEAP");
+        let expected = loud.to_lowercase();
+        let (quiet, allocations) = count_allocations(|| loud.with_lowercased());
+        assert_eq!(allocations, 1);
+        assert_eq!(quiet, *expected);
+        assert_eq!(JavaString::from("ὈΔΥΣΣΕΎΣ").with_lowercased(), "ὀδυσσεύς");
+    }
+
+    #[test]
+    fn common_prefix() {
+        let string = JavaString::from("💖 shared 💖 prefix on the heap");
+        assert_eq!(string.common_prefix("💖 shared 💖 suffix"), "💖 shared 💖 ");
+        assert_eq!(string.common_prefix_len("💖"), 4);
+        // 💔 shares its first three bytes with 💖.
+        assert_eq!(string.common_prefix_len("💔"), 0);
+        assert_eq!(string.common_prefix_len("💖 shared 💔"), 12);
+        assert_eq!(string.common_prefix(&string), string.as_str());
+
+        assert_eq!(string.common_prefix_len("disjoint"), 0);
+        assert_eq!(string.common_prefix(""), "");
+        assert_eq!(JavaString::new().common_prefix("anything"), "");
+    }
+
+    #[test]
+    fn lines_with_offsets() {
+        for text in &[
+            "first\nsecond 💖\r\n\r\n\nlast",
+            "trailing newline\r\n",
+            "\n\n",
+            "no newline",
+            "",
+        ] {
+            let string = JavaString::from(*text);
+            let lines: Vec<_> = string.lines_with_offsets().collect();
+
+            assert!(lines.iter().map(|&(_, line)| line).eq(text.lines()));
+            for &(offset, line) in &lines {
+                assert!(text[offset..].starts_with(line));
+                assert!(offset == 0 || text[..offset].ends_with('\n'));
+            }
+        }
+
+        let string = JavaString::from("first\nsecond 💖\r\n\r\n\nlast\n");
+        let lines: Vec<_> = string.lines_with_offsets().collect();
+        assert_eq!(
+            lines,
+            [
+                (0, "first"),
+                (6, "second 💖"),
+                (19, ""),
+                (21, ""),
+                (22, "last"),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_into() {
+        let mut out = JavaString::from("short ");
+        JavaString::from("and now on the heap 💖")
+            .write_into(&mut out)
+            .unwrap();
+        assert_eq!(out, "short and now on the heap 💖");
+
+        let mut out = String::new();
+        JavaString::new().write_into(&mut out).unwrap();
+        assert_eq!(out, "");
+    }
+
+    #[test]
+    fn extend_allocations() {
+        const COUNT: usize = 10_000;
+        let log = (usize::BITS - COUNT.leading_zeros()) as usize;
+
+        let mut string = JavaString::from("prefix ");
+        let ((), allocations) = count_allocations(|| string.extend((0..COUNT).map(|_| 'a')));
+        assert!(allocations <= 2, "{} allocations", allocations);
+        assert_eq!(string.len(), 7 + COUNT);
+        assert!(string.starts_with("prefix aaa"));
+
+        // Filtering hides the length, so the buffer has to grow as it goes.
+        let mut string = JavaString::new();
+        let ((), allocations) =
+            count_allocations(|| string.extend((0..COUNT).map(|_| "ab").filter(|_| true)));
+        assert!(allocations <= log + 2, "{} allocations", allocations);
+        assert_eq!(string.len(), 2 * COUNT);
+
+        let mut string = JavaString::from("💖");
+        string.extend(vec!["é", "", "x"]);
+        string.extend("yz".chars());
+        assert_eq!(string, "💖éxyz");
+    }
+
+    #[test]
+    fn truncate_split_off_round_trip() {
+        let max = RawJavaString::max_intern_len();
+        for text in &[
+            "",
+            "short 💖",
+            "a string with é and 💖 that lives on the heap",
+        ] {
+            let original = JavaString::from(*text);
+            let boundaries = (0..=text.len()).filter(|&idx| text.is_char_boundary(idx));
+
+            for idx in boundaries {
+                let mut head = original.clone();
+                let tail = head.clone().split_off(idx);
+                head.truncate(idx);
+
+                assert_eq!(head, text[..idx]);
+                assert_eq!(tail, text[idx..]);
+                assert_eq!(head.data.is_interned(), idx <= max);
+                assert_eq!(tail.data.is_interned(), text.len() - idx <= max);
+
+                head.push_str(&tail);
+                assert_eq!(head, original);
+            }
+        }
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut string = JavaString::new();
+        for ch in "ab💖 and enough to reach the heap".chars() {
+            string.push(ch);
+        }
+        assert_eq!(string, "ab💖 and enough to reach the heap");
+
+        let mut popped: Vec<_> = core::iter::from_fn(|| string.pop()).collect();
+        popped.reverse();
+        assert_eq!(
+            popped.into_iter().collect::<String>(),
+            "ab💖 and enough to reach the heap"
+        );
+        assert_eq!(string, "");
+        assert_eq!(string.pop(), None);
+    }
+
+    #[test]
+    fn from_char_slice() {
+        let chars: Vec<char> = "a💖 mixes ASCII, é, and 𝄞 astral chars".chars().collect();
+        let (string, allocations) = count_allocations(|| JavaString::from_char_slice(&chars));
+        assert_eq!(string, "a💖 mixes ASCII, é, and 𝄞 astral chars");
+        assert_eq!(allocations, 1);
+
+        let (string, allocations) = count_allocations(|| JavaString::from_char_slice(&['💖', 'x']));
+        assert_eq!(string, "💖x");
+        assert!(string.data.is_interned());
+        assert_eq!(allocations, 0);
+
+        let string = JavaString::from_char_slice(&[]);
+        assert_eq!(string, "");
+        assert!(string.data.is_interned());
+    }
+
+    #[test]
+    fn clone_into_vec() {
+        let strings = [
+            JavaString::from("a string that lives on the heap"),
+            JavaString::from("short 💖"),
+            JavaString::new(),
+            JavaString::from("another heap string, a bit shorter"),
+        ];
+
+        let mut buf = Vec::with_capacity(64);
+        let ptr = buf.as_ptr();
+        let ((), allocations) = count_allocations(|| {
+            for string in &strings {
+                string.clone_into_vec(&mut buf);
+                assert_eq!(buf, string.as_bytes());
+            }
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(buf.as_ptr(), ptr);
+
+        assert_eq!(strings[1].to_owned_bytes(), "short 💖".as_bytes());
+    }
+
+    #[test]
+    fn split_at_mut() {
+        for (text, expected) in &[
+            ("abc💖DEF", "ABC💖def"),
+            ("abc💖DEF, on the heap", "ABC💖def, on the heap"),
+        ] {
+            let mut string = JavaString::from(*text);
+            let (left, right) = string.split_at_mut(7);
+            assert_eq!((&*left, &*right), text.split_at(7));
+
+            left.make_ascii_uppercase();
+            right.make_ascii_lowercase();
+            assert_eq!(string, *expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 4 is not a char boundary")]
+    fn split_at_mut_off_boundary() {
+        JavaString::from("abc💖").split_at_mut(4);
+    }
+
+    #[test]
+    fn retain_ascii() {
+        let mut string = JavaString::from("Some-Identifier_With spaces, on the heap!");
+        string.retain_ascii(|b| b.is_ascii_alphanumeric() || b == b'_');
+        assert_eq!(string, "SomeIdentifier_Withspacesontheheap");
+
+        string.retain_ascii(|b| b.is_ascii_uppercase());
+        assert_eq!(string, "SIW");
+        assert!(string.data.is_interned());
+
+        string.retain_ascii(|_| false);
+        assert_eq!(string, "");
+    }
+
+    #[test]
+    fn retain_ascii_rejects_non_ascii() {
+        let mut string = JavaString::from("naïve");
+        let mut called = false;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            string.retain_ascii(|_| {
+                called = true;
+                false
+            })
+        }));
+
+        assert!(result.is_err());
+        assert!(!called);
+        assert_eq!(string, "naïve");
+    }
+
+    #[test]
+    fn with_exact() {
+        let pattern = "0123456789abcdef";
+        let fill = |buf: &mut [u8]| {
+            for (idx, b) in buf.iter_mut().enumerate() {
+                *b = pattern.as_bytes()[idx % pattern.len()];
+            }
+        };
+
+        let (string, allocations) = count_allocations(|| JavaString::with_exact(100, fill));
+        let string = string.unwrap();
+        assert_eq!(allocations, 1);
+        assert_eq!(string, &pattern.repeat(7)[..100]);
+
+        let (string, allocations) = count_allocations(|| JavaString::with_exact(10, fill));
+        assert_eq!(allocations, 0);
+        assert_eq!(string.unwrap(), "0123456789");
+
+        let err = JavaString::with_exact(3, |buf| buf.copy_from_slice(b"a\xffb")).unwrap_err();
+        assert_eq!(err.as_bytes(), b"a\xffb");
+        assert_eq!(err.utf8_error().valid_up_to(), 1);
+
+        assert_eq!(JavaString::with_exact(2, |_| {}).unwrap(), "\0\0");
+    }
+
+    #[test]
+    fn push_jstring() {
+        let mut string = JavaString::from("short ");
+        assert!(string.data.is_interned());
+
+        let heap = JavaString::from("and a string that lives on the heap 💖");
+        string.push_jstring(&heap);
+        assert_eq!(string, "short and a string that lives on the heap 💖");
+        assert!(!string.data.is_interned());
+
+        let copy = string.clone();
+        string.push_jstring(&copy);
+        assert_eq!(string.len(), 2 * copy.len());
+
+        string.push_jstring(&JavaString::new());
+        assert_eq!(string.len(), 2 * copy.len());
+    }
+
+    #[test]
+    fn first_and_last_char() {
+        let empty = JavaString::new();
+        assert_eq!((empty.first_char(), empty.last_char()), (None, None));
+        assert_eq!(empty.char_at_or_default(0, '?'), '?');
+
+        let single = JavaString::from("€");
+        assert_eq!(
+            (single.first_char(), single.last_char()),
+            (Some('€'), Some('€'))
+        );
+
+        let string = JavaString::from("💖 multibyte at both ends, on the heap é");
+        assert_eq!(string.first_char(), Some('💖'));
+        assert_eq!(string.last_char(), Some('é'));
+
+        let boundaries: Vec<_> = (0..=string.len())
+            .map(|idx| string.char_at_or_default(idx, '?'))
+            .collect();
+        assert_eq!(boundaries[..6], ['💖', '?', '?', '?', ' ', 'm']);
+        assert_eq!(boundaries[string.len() - 2..], ['é', '?', '?']);
+    }
+
+    #[test]
+    fn trim_matches_char() {
+        let string = JavaString::from("//a/path/that/lives/on/the/heap////");
+        assert_eq!(
+            string.trim_end_matches_char('/'),
+            "//a/path/that/lives/on/the/heap"
+        );
+        assert_eq!(
+            string.trim_start_matches_char('/'),
+            "a/path/that/lives/on/the/heap////"
+        );
+
+        let string = JavaString::from("💖💖ok💖💖💖");
+        assert_eq!(string.trim_end_matches_char('💖'), "💖💖ok");
+        assert_eq!(string.trim_start_matches_char('💖'), "ok💖💖💖");
+        assert_eq!(string.trim_end_matches_char('💔'), string.as_str());
+
+        let string = JavaString::from("////");
+        assert_eq!(string.trim_end_matches_char('/'), "");
+        assert_eq!(string.trim_start_matches_char('/'), "");
+    }
+
+    #[test]
+    fn random_operations_match_string() {
+        const STEPS: usize = if cfg!(miri) { 300 } else { 5_000 };
+        let pieces = ["", "a", "é", "💖", "0123456789", "a longer piece of text"];
+
+        let before = live_bytes();
+        let mut rng = XorShift(0x5851_f42d_4c95_7f2d);
+        let mut string = JavaString::new();
+        let mut expected = String::new();
+        let mut clones = Vec::new();
+
+        for _ in 0..STEPS {
+            match rng.next() % 8 {
+                0 | 1 => {
+                    let piece = pieces[rng.next() % pieces.len()];
+                    string.push_str(piece);
+                    expected.push_str(piece);
+                }
+                2 => {
+                    let ch = pieces[rng.next() % 4].chars().next().unwrap_or('z');
+                    string.push(ch);
+                    expected.push(ch);
+                }
+                3 => assert_eq!(string.pop(), expected.pop()),
+                4 => {
+                    let mut new_len = rng.next() % (expected.len() + 1);
+                    while !expected.is_char_boundary(new_len) {
+                        new_len -= 1;
+                    }
+                    string.truncate(new_len);
+                    expected.truncate(new_len);
+                }
+                5 if rng.next() % 4 == 0 => {
+                    string.clear();
+                    expected.clear();
+                }
+                5 => {
+                    string.reserve(rng.next() % 64);
+                    string.shrink_to_fit();
+                }
+                _ => {
+                    clones.push(string.clone());
+                    if clones.len() > 8 {
+                        clones.remove(rng.next() % clones.len());
+                    }
+                }
+            }
+
+            assert_eq!(string, *expected);
+            assert_eq!(
+                string.data.is_interned(),
+                string.len() <= RawJavaString::max_intern_len()
+            );
+        }
+
+        drop((string, clones));
+        drop(expected);
+        assert_eq!(live_bytes(), before);
+    }
+
+    #[test]
+    fn multi_chunk_mutations() {
+        let mut string = JavaString::from("foo");
+        string.push_str("bar");
+        assert_eq!(string, "foobar");
+        assert!(string.data.is_interned());
+
+        string.push_str("");
+        string.push_str(" and a chunk long enough for the heap");
+        assert_eq!(string, "foobar and a chunk long enough for the heap");
+        assert!(!string.data.is_interned());
+
+        string.insert_str(3, "");
+        string.insert_str(0, "💖");
+        string.insert(7, '-');
+        assert_eq!(string, "💖foo-bar and a chunk long enough for the heap");
+
+        assert_eq!(string.remove(7), '-');
+        assert_eq!(string.remove(0), '💖');
+        string.replace_range(6.., "");
+        assert_eq!(string, "foobar");
+        assert!(string.data.is_interned());
+
+        string.replace_range(..0, "");
+        string.replace_range(3..3, "|");
+        assert_eq!(string, "foo|bar");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn from_odd_string_buffers() {
+        let before = live_bytes();
+        for text in &["short 💖", "a string long enough to need the heap 💖"] {
+            let string = with_odd_byte_buffers(|| String::from(*text));
+            assert_eq!(string.as_ptr() as usize % 2, 1);
+
+            let java_string = JavaString::from(string);
+            assert_eq!(java_string, *text);
+            assert_eq!(
+                java_string.data.is_interned(),
+                text.len() <= RawJavaString::max_intern_len()
+            );
+            assert_eq!(java_string.clone().into_bytes(), text.as_bytes());
+
+            let mut bytes = with_odd_byte_buffers(|| text.as_bytes().to_vec());
+            let from_vec = JavaString::from_utf8_mut(&mut bytes).unwrap();
+            assert_eq!(from_vec, java_string);
+        }
+        assert_eq!(live_bytes(), before);
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<JavaString>();
+
+        let string = JavaString::from("moved to another thread, on the heap");
+        let handle = std::thread::spawn(move || string.len());
+        assert_eq!(handle.join().unwrap(), 36);
+
+        let shared = std::sync::Arc::new(JavaString::from("shared between threads, on the heap"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = std::sync::Arc::clone(&shared);
+                std::thread::spawn(move || shared.to_uppercase())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(
+                handle.join().unwrap(),
+                "SHARED BETWEEN THREADS, ON THE HEAP"
+            );
+        }
+    }
+
+    #[test]
+    fn hash_map_keys() {
+        use std::collections::{HashMap, HashSet};
+
+        let heap_text = "a key that lives on the heap";
+        let mut map = HashMap::new();
+        map.insert(JavaString::from("short"), 1);
+        map.insert(JavaString::from(heap_text), 2);
+
+        assert_eq!(map.get(&JavaString::from("short")), Some(&1));
+        assert_eq!(map.get(&JavaString::from(heap_text)), Some(&2));
+        assert_eq!(map.get(&JavaString::from("missing")), None);
+
+        // The same text built by different routes lands on the same key.
+        let mut built = JavaString::from("a key that lives");
+        built.push_str(" on the heap");
+        assert_eq!(map.insert(built, 3), Some(2));
+        assert_eq!(map.len(), 2);
+
+        let set: HashSet<_> = ["x", "y", "x", heap_text, heap_text]
+            .iter()
+            .map(|&text| JavaString::from(text))
+            .collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn insert_str_around_emoji() {
+        let mut string = JavaString::from("💖💖");
+        string.insert_str(4, "ab");
+        string.insert_str(0, "<");
+        string.insert_str(string.len(), ">");
+        assert_eq!(string, "<💖ab💖>");
+
+        for idx in 2..4 {
+            let mut string = JavaString::from("a💖b");
+            assert_eq!(
+                string.checked_insert_str(idx, "x"),
+                Err(BoundsError::NotCharBoundary { index: idx })
+            );
+            assert_eq!(string, "a💖b");
+        }
+    }
+
+    #[test]
+    fn insert_str_allocations() {
+        let max = RawJavaString::max_intern_len();
+        let mut string = JavaString::from("💖");
+        let filler = "y".repeat(max - string.len());
+
+        // Still short enough to be interned, so nothing touches the heap.
+        let ((), allocations) = count_allocations(|| string.insert_str(4, &filler));
+        assert_eq!(allocations, 0);
+        assert!(string.stats().is_interned);
+
+        // Spilling onto the heap takes a single allocation for the whole string.
+        let ((), allocations) = count_allocations(|| string.insert_str(0, &filler));
+        assert_eq!(allocations, 1);
+        assert_eq!(string, [&filler[..], "💖", &filler[..]].concat().as_str());
+    }
+
+    #[test]
+    fn clear_frees_heap() {
+        let mut string = JavaString::from("a string that lives on the heap");
+        let before = live_bytes();
+
+        string.clear();
+        assert!(string.is_empty());
+        assert!(string.stats().is_interned);
+        assert_eq!(before - live_bytes(), 31);
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 3 is not a char boundary")]
+    fn split_off_panics_off_boundary() {
+        JavaString::from("a💖, on the heap this time").split_off(3);
+    }
+
+    #[test]
+    fn as_ref_conversions() {
+        use std::ffi::OsStr;
+        use std::path::Path;
+
+        fn takes_str<S: AsRef<str>>(string: S) -> usize {
+            string.as_ref().len()
+        }
+
+        fn file_name<P: AsRef<Path>>(path: &P) -> Option<&OsStr> {
+            path.as_ref().file_name()
+        }
+
+        for text in &["dir/short", "a/path/to/a/file/that/lives/on/the/heap"] {
+            let string = JavaString::from(*text);
+            assert_eq!(takes_str(&string), text.len());
+            assert_eq!(file_name(&string), Path::new(text).file_name());
+            assert_eq!(AsRef::<OsStr>::as_ref(&string), OsStr::new(text));
+            assert_eq!(AsRef::<[u8]>::as_ref(&string), text.as_bytes());
+            assert_eq!(takes_str(string), text.len());
+        }
+
+        let mut string = JavaString::from("shout");
+        AsMut::<str>::as_mut(&mut string).make_ascii_uppercase();
+        assert_eq!(string, "SHOUT");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn open_file_by_java_string() {
+        let path = JavaString::from(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+        assert!(std::fs::File::open(&path).is_ok());
+        assert!(std::fs::File::open(JavaString::from("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn index_ranges() {
+        for text in &["é💖x", "é💖x, and enough to put this string on the heap"] {
+            let mut string = JavaString::from(*text);
+            assert_eq!(&string[..], *text);
+            assert_eq!(&string[2..6], "💖");
+            assert_eq!(&string[2..], &text[2..]);
+            assert_eq!(&string[..6], "é💖");
+            assert_eq!(&string[2..=5], "💖");
+            assert_eq!(&string[..=1], "é");
+
+            string[6..7].make_ascii_uppercase();
+            string[..].make_ascii_uppercase();
+            assert_eq!(&string[..7], "é💖X");
+        }
+    }
+
+    #[test]
+    fn index_panics_off_boundary() {
+        for text in &["é💖x", "é💖x, and enough to put this string on the heap"] {
+            let string = JavaString::from(*text);
+            for range in &[1..6, 2..5, 3..7] {
+                let range = range.clone();
+                let result = std::panic::catch_unwind(|| string[range].len());
+                assert!(result.is_err());
+            }
+            let result = std::panic::catch_unwind(|| JavaString::from(*text)[..=2].len());
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn add_operators() {
+        let max = RawJavaString::max_intern_len();
+        let piece = JavaString::from("ab💖");
+
+        let (string, allocations) = count_allocations(|| JavaString::from("x") + "y" + &piece);
+        assert_eq!(string, "xyab💖");
+        assert!(string.stats().is_interned);
+        assert_eq!(allocations, 0);
+
+        let mut string = JavaString::new();
+        let mut expected = String::new();
+        while string.len() <= max {
+            let ((), allocations) = count_allocations(|| string += &piece);
+            expected += "ab💖";
+            assert_eq!(string, expected.as_str());
+            assert_eq!(allocations, usize::from(string.len() > max));
+        }
+        assert!(!string.stats().is_interned);
+
+        let ((), allocations) = count_allocations(|| string += "!");
+        assert_eq!(allocations, 1);
+        assert_eq!(string, (expected + "!").as_str());
+    }
+
+    #[test]
+    fn retain_chars() {
+        let mut string = JavaString::from(" a\tstring with\nsome whitespace, on the heap ");
+        string.retain(|c| !c.is_whitespace());
+        assert_eq!(string, "astringwithsomewhitespace,ontheheap");
+
+        let mut string = JavaString::from("💖é💖x💖");
+        let mut seen = Vec::new();
+        string.retain(|c| {
+            seen.push(c);
+            c != '💖'
+        });
+        assert_eq!(string, "éx");
+        assert_eq!(seen, ['💖', 'é', '💖', 'x', '💖']);
+
+        let mut string = JavaString::from("everything is kept, even on the heap");
+        let before = string.as_ptr();
+        string.retain(|_| true);
+        assert_eq!(string, "everything is kept, even on the heap");
+        assert_eq!(string.as_ptr(), before);
+    }
+
+    #[test]
+    fn collect_into_java_string() {
+        use alloc::borrow::Cow;
+
+        let max = RawJavaString::max_intern_len();
+        for text in &["é 💖 x", "a sentence with é and 💖 that lives on the heap"] {
+            let before = live_bytes();
+            let string: JavaString = text.chars().collect();
+            assert_eq!(string, *text);
+            // The intermediate buffer is gone, leaving just the string's own.
+            let heap_len = if text.len() > max { text.len() } else { 0 };
+            assert_eq!(live_bytes() - before, heap_len as isize);
+
+            let words: JavaString = text.split_whitespace().collect();
+            assert_eq!(words, *text.replace(' ', ""));
+
+            let owned: JavaString = text.split(' ').map(String::from).collect();
+            assert_eq!(owned, words);
+
+            let cows: JavaString = text.split(' ').map(Cow::Borrowed).collect();
+            assert_eq!(cows, words);
+
+            let java: JavaString = text.split(' ').map(JavaString::from).collect();
+            assert_eq!(java, words);
+            assert_eq!(java.stats().is_interned, java.len() <= max);
+        }
+
+        assert_eq!(core::iter::empty::<char>().collect::<JavaString>(), "");
+    }
+
+    #[test]
+    fn extend_owned_and_empty() {
+        use alloc::borrow::Cow;
+
+        let max = RawJavaString::max_intern_len();
+        let mut string = JavaString::from("ab");
+        string.extend(vec![String::from("cd"), String::new()]);
+        string.extend(vec![Cow::Borrowed("ef"), Cow::Owned(String::from("💖"))]);
+        assert_eq!(string, "abcdef💖");
+        assert!(string.stats().is_interned);
+
+        // Crossing the intern limit rebuilds the string once. Besides the new
+        // buffer, only the lists of pieces are allocated, never their contents,
+        // and std may reuse the incoming `Vec` for the first of those.
+        let pieces: Vec<JavaString> = (0..max).map(|_| JavaString::from("x")).collect();
+        let ((), allocations) = count_allocations(|| string.extend(pieces));
+        assert!(allocations <= 3, "{} allocations", allocations);
+        assert_eq!(string, ["abcdef💖", &"x".repeat(max)].concat().as_str());
+
+        let pieces = vec!["ab"; 10_000];
+        let mut long = JavaString::from("a string that lives on the heap");
+        let ((), allocations) = count_allocations(|| long.extend(pieces));
+        assert!(allocations <= 3, "{} allocations", allocations);
+        assert_eq!(long.len(), 31 + 20_000);
+
+        // Extending a heap string with nothing keeps its buffer.
+        let before = string.as_ptr();
+        let ((), allocations) = count_allocations(|| {
+            string.extend(core::iter::empty::<char>());
+            string.extend(Vec::<&str>::new());
+        });
+        assert_eq!(allocations, 0);
+        string.extend(vec![JavaString::new(), JavaString::new()]);
+        string.extend(vec![String::new()]);
+        assert_eq!(string.as_ptr(), before);
+    }
+
+    #[test]
+    fn extend_char_range() {
+        let mut string = JavaString::from("letters: ");
+        let ((), allocations) = count_allocations(|| string.extend('a'..='z'));
+        // The range knows its length, so the intermediate buffer is allocated
+        // once, and the string itself is rebuilt once.
+        assert_eq!(allocations, 2);
+        assert_eq!(string, "letters: abcdefghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_extend_ten_thousand_pieces() {
+        use std::time::Instant;
+
+        let pieces: Vec<String> = (0..10_000).map(|i| format!("piece {} ", i)).collect();
+
+        let mut pushed = JavaString::new();
+        let start = Instant::now();
+        for piece in &pieces {
+            pushed.push_str(piece);
+        }
+        println!("push_str per piece: {:?}", start.elapsed());
+
+        let mut extended = JavaString::new();
+        let start = Instant::now();
+        extended.extend(pieces.iter().map(String::as_str));
+        println!("extend(&str):       {:?}", start.elapsed());
+
+        let mut owned = JavaString::new();
+        let cloned = pieces.clone();
+        let start = Instant::now();
+        owned.extend(cloned);
+        println!("extend(String):     {:?}", start.elapsed());
+
+        assert_eq!(extended, pushed);
+        assert_eq!(owned, pushed);
+    }
+
+    #[test]
+    fn fmt_write() {
+        use core::fmt::Write;
+
+        let (key, heart) = ("key", '💖');
+        let mut string = JavaString::new();
+        write!(string, "{}: {:>3}", key, 7).unwrap();
+        assert_eq!(string, "key:   7");
+        assert!(string.stats().is_interned);
+
+        // This write starts out interned and ends up on the heap partway.
+        writeln!(string, ", {:?} and {}", key, heart).unwrap();
+        assert_eq!(string, "key:   7, \"key\" and 💖\n");
+        assert!(!string.stats().is_interned);
+
+        let ((), allocations) = count_allocations(|| string.write_char('é').unwrap());
+        assert_eq!(allocations, 1);
+        assert!(string.ends_with("💖\né"));
+    }
+
+    #[test]
+    fn parse_from_str() {
+        fn parse_all<T: core::str::FromStr>(texts: &[&str]) -> Vec<T>
+        where
+            T::Err: fmt::Debug,
+        {
+            texts.iter().map(|text| text.parse().unwrap()).collect()
+        }
+
+        let (string, allocations) = count_allocations(|| "abc".parse::<JavaString>().unwrap());
+        assert_eq!(string, "abc");
+        assert!(string.stats().is_interned);
+        assert_eq!(allocations, 0);
+
+        let texts = ["short", "a string that lives on the heap"];
+        let parsed: Vec<JavaString> = parse_all(&texts);
+        assert_eq!(parsed, texts);
+        assert!(!parsed[1].stats().is_interned);
+    }
+
+    #[test]
+    fn collect_chars() {
+        let string: JavaString = "héllo".chars().collect();
+        assert_eq!(string, "héllo");
+        assert!(string.stats().is_interned);
+
+        let (string, allocations) =
+            count_allocations(|| core::iter::empty::<char>().collect::<JavaString>());
+        assert_eq!(string, "");
+        assert!(string.stats().is_interned);
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn collect_pieces_in_order() {
+        let pieces = ["one", "-", "two", "-", "3"];
+        let owned: Vec<String> = pieces.iter().map(|&piece| piece.into()).collect();
+        let java: Vec<JavaString> = pieces.iter().map(|&piece| piece.into()).collect();
+
+        // Small results are interned, so the list of pieces is the only thing
+        // that could need an allocation.
+        let (from_strs, allocations) = count_allocations(|| pieces.iter().copied().collect());
+        assert_eq!(allocations, 1);
+        let (from_strings, allocations) = count_allocations(|| owned.into_iter().collect());
+        assert!(allocations <= 1);
+        let (from_java, allocations) = count_allocations(|| java.into_iter().collect());
+        assert!(allocations <= 1);
+
+        for string in &[from_strs, from_strings, from_java] {
+            let string: &JavaString = string;
+            assert_eq!(string, "one-two-3");
+            assert!(string.stats().is_interned);
+        }
+    }
+
+    #[test]
+    fn eq_string_types_both_orders() {
+        use alloc::borrow::Cow;
+
+        for text in &["short", "a string that lives on the heap"] {
+            let string = JavaString::from(*text);
+            let owned = String::from(*text);
+            let borrowed: Cow<str> = Cow::Borrowed(text);
+            let cow_owned: Cow<str> = Cow::Owned(owned.clone());
+
+            assert_eq!(string, owned);
+            assert_eq!(owned, string);
+            assert_eq!(string, borrowed);
+            assert_eq!(borrowed, string);
+            assert_eq!(string, cow_owned);
+            assert_eq!(cow_owned, string);
+            assert_eq!(*text, string);
+            assert_eq!(**text, string);
+            assert_eq!(string, JavaString::from(owned.as_str()));
+
+            let reference = &string;
+            assert_eq!(reference, owned);
+            assert_eq!(owned, reference);
+            assert_eq!(reference, *text);
+            assert_eq!(*text, reference);
+            assert_eq!(reference, **text);
+            assert_eq!(**text, reference);
+
+            let other = format!("{}!", text);
+            assert_ne!(string, other);
+            assert_ne!(other, string);
+            assert_ne!(other.as_str(), string);
+            assert_ne!(reference, other);
+            assert_ne!(other, reference);
+            assert_ne!(Cow::Borrowed(other.as_str()), string);
+        }
+    }
+
+    #[test]
+    fn add_str_and_char() {
+        assert_eq!(JavaString::from("foo") + "bar", "foobar");
+
+        let mut string = JavaString::from("foo");
+        string += "bar";
+        string += '💖';
+        assert_eq!(string, "foobar💖");
+    }
+
+    #[test]
+    fn ord_against_str_types() {
+        let max = RawJavaString::max_intern_len();
+        let interned_text = "m".repeat(max);
+        let heap_text = format!("{}a", interned_text);
+        let interned = JavaString::from(interned_text.as_str());
+        let heap = JavaString::from(heap_text.as_str());
+        assert!(interned.stats().is_interned);
+        assert!(!heap.stats().is_interned);
+
+        // A shared prefix puts the shorter, interned string first.
+        assert!(interned < heap);
+        assert!(interned < *heap_text.as_str());
+        assert!(interned < heap_text.as_str());
+        assert!(interned < heap_text);
+        assert!(*interned_text.as_str() < heap);
+        assert!(interned_text.as_str() < heap);
+        assert!(interned_text < heap);
+
+        assert!(heap < "n");
+        assert!("n" > heap);
+        assert!(heap > *"m");
+        assert_eq!(
+            interned.partial_cmp(interned_text.as_str()),
+            Some(core::cmp::Ordering::Equal)
+        );
+        assert_eq!(
+            String::from("é").partial_cmp(&JavaString::from("z")),
+            "é".partial_cmp("z")
+        );
+    }
+
+    #[test]
+    fn round_trip_through_thread() {
+        use std::sync::mpsc::channel;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RawJavaString>();
+
+        let (to_worker, from_main) = channel::<JavaString>();
+        let (to_main, from_worker) = channel();
+        let worker = std::thread::spawn(move || {
+            for mut string in from_main {
+                string.push('!');
+                to_main.send(string).unwrap();
+            }
+        });
+
+        for text in &["short", "a string that lives on the heap"] {
+            to_worker.send(JavaString::from(*text)).unwrap();
+            let string = from_worker.recv().unwrap();
+            assert_eq!(string, format!("{}!", text));
+        }
+        drop(to_worker);
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn truncate_matches_string() {
+        for text in &["a💖b", "a💖b, and enough to put this string on the heap"] {
+            let mut string = JavaString::from(*text);
+            let before = string.as_ptr();
+            string.truncate(text.len());
+            string.truncate(text.len() + 1);
+            string.truncate(usize::MAX);
+            assert_eq!(string, *text);
+            assert_eq!(string.as_ptr(), before);
+
+            for cut in 2..5 {
+                let mut string = JavaString::from(*text);
+                assert_eq!(
+                    string.checked_truncate(cut),
+                    Err(BoundsError::NotCharBoundary { index: cut })
+                );
+                assert_eq!(string, *text);
+            }
+
+            string.truncate(5);
+            assert_eq!(string, "a💖");
+            string.truncate(1);
+            assert_eq!(string, "a");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 3 is not a char boundary")]
+    fn truncate_panics_inside_emoji() {
+        JavaString::from("a💖b").truncate(3);
+    }
+
+    #[test]
+    fn boxed_str_round_trip() {
+        for text in &["", "short", "a string that lives on the heap"] {
+            let boxed = JavaString::from(*text).into_boxed_str();
+            assert_eq!(&*boxed, *text);
+
+            let before = live_bytes();
+            let string = JavaString::from(boxed);
+            assert_eq!(string, *text);
+            assert_eq!(
+                string.stats().is_interned,
+                string.len() <= RawJavaString::max_intern_len()
+            );
+            // The box is freed, leaving only the string's own buffer behind.
+            assert_eq!(
+                live_bytes() - before,
+                string.stats().allocated_bytes as isize - text.len() as isize
+            );
+        }
+    }
+
+    #[test]
+    fn in_place_mutation() {
+        // Run under miri to check that mutable access to interned bytes, which
+        // live inside the struct itself, is derived from `&mut self`.
+        for text in &["short", "a string that lives on the heap"] {
+            let mut string = JavaString::from(*text);
+            string.as_mut_str().make_ascii_uppercase();
+            assert_eq!(string, *text.to_ascii_uppercase());
+
+            string.make_ascii_lowercase();
+            assert_eq!(string, *text);
+
+            let bytes = string.data.get_bytes_mut();
+            bytes[0] = b'S';
+            bytes[bytes.len() - 1] = b'!';
+            let first = string.as_bytes()[0];
+            assert_eq!(first, b'S');
+            assert!(string.ends_with('!'));
+        }
+    }
+
+    #[test]
+    fn cow_conversions() {
+        use alloc::borrow::Cow;
+
+        for text in &["short", "a string that lives on the heap"] {
+            let borrowed = JavaString::from(Cow::Borrowed(*text));
+            let owned = JavaString::from(Cow::<str>::Owned(String::from(*text)));
+            assert_eq!(borrowed, *text);
+            assert_eq!(owned, borrowed);
+
+            match Cow::from(&owned) {
+                Cow::Borrowed(string) => assert_eq!(string.as_ptr(), owned.as_ptr()),
+                Cow::Owned(_) => panic!("expected a borrowed Cow"),
+            }
+        }
+    }
+
+    #[test]
+    fn into_string() {
+        for text in &["", "short", "a string with é and 💖 that lives on the heap"] {
+            let (string, allocations) = count_allocations(|| String::from(JavaString::from(*text)));
+            assert_eq!(string, *text);
+            assert_eq!(string.capacity(), text.len());
+            // The contents are copied once, plus the `JavaString`'s own buffer
+            // if it needed one.
+            let heap = text.len() > RawJavaString::max_intern_len();
+            assert_eq!(
+                allocations,
+                usize::from(!text.is_empty()) + usize::from(heap)
+            );
+
+            assert_eq!(JavaString::from(*text).into_string(), string);
+        }
+    }
+
+    #[test]
+    fn from_utf8_lossy_owned() {
+        let valid = "valid é and 💖, long enough for the heap";
+        let (string, allocations) =
+            count_allocations(|| JavaString::from_utf8_lossy_owned(valid.as_bytes()));
+        assert_eq!(string, valid);
+        assert_eq!(allocations, 1);
+
+        let (string, allocations) = count_allocations(|| JavaString::from_utf8_lossy_owned(b"ok"));
+        assert_eq!(string, "ok");
+        assert_eq!(allocations, 0);
+
+        let cases: &[&[u8]] = &[
+            b"a\x80b",
+            b"\x80",
+            b"trailing \xf0\x9f\x92",
+            b"trailing \xe2\x82",
+            &[0xff; 40],
+            b"caf\xe9 au lait, long enough for the heap \xf0\x9f",
+        ];
+        for bytes in cases {
+            let string = JavaString::from_utf8_lossy_owned(bytes);
+            assert_eq!(string, *String::from_utf8_lossy(bytes));
+            assert_eq!(
+                string.stats().is_interned,
+                string.len() <= RawJavaString::max_intern_len()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn temp_file_by_java_string() {
+        use std::ffi::OsString;
+        use std::path::PathBuf;
+
+        let dir = std::env::temp_dir();
+        let path = JavaString::from(
+            dir.join(format!("jstring-{}.txt", std::process::id()))
+                .to_str()
+                .unwrap(),
+        );
+
+        std::fs::write(&path, "contents").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+
+        let path_buf = PathBuf::from(path.clone());
+        let os_string = OsString::from(path.clone());
+        assert_eq!(path_buf.as_os_str(), os_string.as_os_str());
+        assert_eq!(os_string, path.as_str());
+
+        std::fs::remove_file(path_buf).unwrap();
+        assert!(std::fs::File::open(&path).is_err());
+    }
+
+    #[test]
+    fn from_utf16_lossy() {
+        let clef = [0xD834, 0xDD1E];
+        let cases: &[&[u16]] = &[
+            &[0x61, 0xD834, 0x62],
+            &[0x61, 0xDD1E, 0x62],
+            &[0x61, 0xD834],
+            &[0xD834, 0xDD1E, 0x61, 0xD834],
+            &clef,
+            &[0xDD1E, 0xD834],
+        ];
+        for v in cases {
+            let (string, allocations) = count_allocations(|| JavaString::from_utf16_lossy(v));
+            assert_eq!(string, *String::from_utf16_lossy(v));
+            assert!(string.stats().is_interned);
+            assert_eq!(allocations, 0);
+        }
+
+        let long: Vec<u16> = "a string that lives on the heap 💖"
+            .encode_utf16()
+            .collect();
+        let (string, allocations) = count_allocations(|| JavaString::from_utf16(&long).unwrap());
+        assert_eq!(string, "a string that lives on the heap 💖");
+        assert_eq!(allocations, 1);
+
+        let err = JavaString::from_utf16(&[0x61, 0xD834]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            String::from_utf16(&[0xD834]).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn borrowed_str_lookups() {
+        use std::collections::{BTreeMap, HashMap, HashSet};
+
+        let keys = ["short", "a key that lives on the heap"];
+        let hash: HashMap<JavaString, usize> = keys
+            .iter()
+            .enumerate()
+            .map(|(idx, &key)| (JavaString::from(key), idx))
+            .collect();
+        let btree: BTreeMap<JavaString, usize> = hash.clone().into_iter().collect();
+
+        assert_eq!(hash.get("short"), Some(&0));
+        assert_eq!(hash.get("a key that lives on the heap"), Some(&1));
+        assert_eq!(hash.get("missing"), None);
+        assert_eq!(btree.get("short"), Some(&0));
+        assert_eq!(btree.get("a key that lives on the heap"), Some(&1));
+        assert_eq!(
+            btree
+                .range::<str, _>((Bound::Included("b"), Bound::Unbounded))
+                .count(),
+            1
+        );
+
+        let set: HashSet<JavaString> = keys.iter().map(|&key| key.into()).collect();
+        assert!(set.contains("short"));
+        assert!(!set.contains("shorter"));
+    }
+
+    #[test]
+    fn from_utf16_bytes() {
+        let text = "\u{feff}bom, é and 𝄞, long enough for the heap";
+        let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+
+        let (string, allocations) = count_allocations(|| JavaString::from_utf16le(&le).unwrap());
+        assert_eq!(string, text);
+        assert_eq!(allocations, 1);
+        assert_eq!(JavaString::from_utf16be(&be).unwrap(), text);
+        assert_eq!(JavaString::from_utf16le_lossy(&le), text);
+        assert_eq!(JavaString::from_utf16be_lossy(&be), text);
+
+        // Odd lengths are an error, or a trailing replacement character.
+        assert!(JavaString::from_utf16le(&le[..3]).is_err());
+        assert!(JavaString::from_utf16be(&be[..3]).is_err());
+        assert_eq!(
+            JavaString::from_utf16le_lossy(&le[..5]),
+            "\u{feff}b\u{fffd}"
+        );
+        assert_eq!(
+            JavaString::from_utf16be_lossy(&be[..5]),
+            "\u{feff}b\u{fffd}"
+        );
+
+        // Decoding with the wrong byte order can still be valid, but different.
+        let swapped = JavaString::from_utf16be(&[0x61, 0x00, 0x62, 0x00]).unwrap();
+        assert_eq!(swapped, "\u{6100}\u{6200}");
+        assert_ne!(JavaString::from_utf16le(&be).unwrap(), text);
+
+        let lone = [0x00, 0xD8, 0x61, 0x00];
+        assert!(JavaString::from_utf16le(&lone).is_err());
+        let lossy = JavaString::from_utf16le_lossy(&lone);
+        assert_eq!(lossy, "\u{fffd}a");
+        assert!(lossy.stats().is_interned);
+    }
+
+    #[test]
+    fn convert_out_round_trips() {
+        use alloc::borrow::Cow;
+
+        let max = RawJavaString::max_intern_len();
+        for text in &["short".to_string(), "x".repeat(max), "é💖".repeat(600)] {
+            let string = JavaString::from(text.clone());
+            assert_eq!(String::from(string.clone()), *text);
+            assert_eq!(string.clone().into_string(), *text);
+            assert_eq!(&*string.clone().into_boxed_str(), text.as_str());
+            assert_eq!(Vec::from(string.clone()), text.as_bytes());
+
+            let cow: Cow<'static, str> = string.into();
+            assert!(matches!(cow, Cow::Owned(_)));
+            assert_eq!(cow, text.as_str());
+        }
+    }
+
+    #[test]
+    fn from_std_types() {
+        use alloc::borrow::Cow;
+
+        for &ch in &['a', 'é', '€', '💖'] {
+            let (string, allocations) = count_allocations(|| JavaString::from(ch));
+            assert_eq!(allocations, 0);
+            assert_eq!(string.chars().collect::<Vec<_>>(), [ch]);
+            assert!(string.stats().is_interned);
+        }
+
+        for text in &["short", "a string that lives on the heap"] {
+            let mut owned = String::from(*text);
+            assert_eq!(JavaString::from(&owned), *text);
+            assert_eq!(JavaString::from(owned.as_mut_str()), *text);
+            assert_eq!(JavaString::from(owned.clone().into_boxed_str()), *text);
+
+            // Either arm of a `Cow` is copied exactly once, if it needs the heap.
+            let heap = usize::from(text.len() > RawJavaString::max_intern_len());
+            let borrowed = Cow::Borrowed(*text);
+            let (string, allocations) = count_allocations(|| JavaString::from(borrowed));
+            assert_eq!(string, *text);
+            assert_eq!(allocations, heap);
+
+            let cow_owned: Cow<str> = Cow::Owned(owned);
+            let (string, allocations) = count_allocations(|| JavaString::from(cow_owned));
+            assert_eq!(string, *text);
+            assert_eq!(allocations, heap);
+        }
+    }
+
+    #[test]
+    fn parse_with_question_mark() {
+        fn parse_name(text: &str) -> Result<JavaString, core::convert::Infallible> {
+            let name: JavaString = text.trim().parse()?;
+            Ok(name)
+        }
+
+        assert_eq!(parse_name("  alice ").unwrap(), "alice");
+        assert_eq!(
+            parse_name("a name long enough for the heap").unwrap(),
+            "a name long enough for the heap"
+        );
+    }
+
+    #[test]
+    fn insert_char_allocations() {
+        let mut string = JavaString::from("é€");
+        let ((), allocations) = count_allocations(|| string.insert(2, '💖'));
+        assert_eq!(allocations, 0);
+        assert_eq!(string, "é💖€");
+
+        let mut string = JavaString::from("a string that lives on the heap");
+        let ((), allocations) = count_allocations(|| string.insert(1, '💖'));
+        assert_eq!(allocations, 1);
+        assert_eq!(string, "a💖 string that lives on the heap");
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 3 is not a char boundary")]
+    fn insert_str_panics_off_boundary() {
+        JavaString::from("a💖b").insert_str(3, "x");
     }
 }
@@ -1,7 +1,8 @@
 use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::mem;
 use core::ops::{Deref, DerefMut};
-use core::ptr::NonNull;
+use core::ptr::{self, NonNull};
 use core::slice;
 
 /// String whose contents can't be mutated, just like how Java strings work.
@@ -10,9 +11,28 @@ use core::slice;
 /// No amortization here buddy.
 ///
 /// Maintains invariants:
-/// 1. Internal pointer is always big endian if valid
-/// 2. `data` is only a valid pointer if its big-endian representation is aligned
-///    to 2 bytes.
+/// 1. `data` is a real pointer to the heap buffer, with its provenance, if the
+///    string isn't interned. Heap buffers are aligned to 2 bytes.
+/// 2. Otherwise, the lowest bit of `data` is set, its lowest byte holds the
+///    length shifted left by one, and the string's bytes fill the rest of the
+///    struct.
+///
+/// The fields are ordered so that the lowest byte of `data` is the first byte
+/// of the struct on little-endian targets and the last on big-endian ones,
+/// which keeps the interned bytes contiguous. Both fields are a word wide, so
+/// `#[repr(C)]` leaves no padding between them.
+#[cfg(target_endian = "little")]
+#[repr(C)]
+#[derive(Eq)]
+pub struct RawJavaString {
+    data: NonNull<u8>,
+    len: usize,
+}
+
+/// String whose contents can't be mutated, just like how Java strings work.
+///
+/// See the little-endian definition for the invariants.
+#[cfg(target_endian = "big")]
 #[repr(C)]
 #[derive(Eq)]
 pub struct RawJavaString {
@@ -20,6 +40,10 @@ pub struct RawJavaString {
     data: NonNull<u8>,
 }
 
+/// Offset of the first interned byte from the start of the struct: the
+/// length byte comes first on little-endian targets.
+const INTERNED_OFFSET: usize = cfg!(target_endian = "little") as usize;
+
 impl RawJavaString {
     /// Returns the maxiumum length of an interned string on the target architecture.
     #[inline(always)]
@@ -35,18 +59,22 @@ impl RawJavaString {
 
     #[inline(always)]
     pub fn read_ptr(&self) -> *mut u8 {
-        usize::from_be(self.data.as_ptr() as usize) as *mut u8
+        self.data.as_ptr()
     }
 
     #[inline(always)]
     pub fn write_ptr(&mut self, ptr: *mut u8) {
-        self.data = NonNull::new(usize::to_be(ptr as usize) as *mut u8)
-            .expect("Wrote null to JavaString pointer.");
+        self.data = NonNull::new(ptr).expect("Wrote null to JavaString pointer.");
     }
 
+    /// Writes `ptr` without checking that it's non-null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must not be null.
     #[inline(always)]
     pub unsafe fn write_ptr_unchecked(&mut self, ptr: *mut u8) {
-        self.data = NonNull::new_unchecked(usize::to_be(ptr as usize) as *mut u8);
+        self.data = NonNull::new_unchecked(ptr);
     }
 
     /// Returns the length of this string.
@@ -59,6 +87,12 @@ impl RawJavaString {
         }
     }
 
+    /// Returns whether or not this string is empty.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the current memory layout of this object. If None, then we're looking
     /// at an interned string.
     #[inline(always)]
@@ -70,48 +104,87 @@ impl RawJavaString {
         }
     }
 
-    /// Returns a reference to the contents of this string as a slice of bytes.
-    pub fn get_bytes(&self) -> &[u8] {
-        let (ptr, len) = if self.is_interned() {
-            let len = ((self.read_ptr() as usize as u8) >> 1) as usize;
-            let ptr = (&self.len) as *const usize as *const u8 as *mut u8;
-            (ptr, len)
+    /// Returns a pointer to the first byte of this string. Interned bytes are
+    /// stored in the string itself, across both fields, so the pointer is
+    /// derived from the whole struct rather than either field.
+    #[inline(always)]
+    fn data_ptr(&self) -> *const u8 {
+        if self.is_interned() {
+            (self as *const Self as *const u8).wrapping_add(INTERNED_OFFSET)
         } else {
-            (self.read_ptr(), self.len)
-        };
+            self.read_ptr()
+        }
+    }
+
+    /// Mutable version of `data_ptr`.
+    #[inline(always)]
+    fn data_ptr_mut(&mut self) -> *mut u8 {
+        if self.is_interned() {
+            (self as *mut Self as *mut u8).wrapping_add(INTERNED_OFFSET)
+        } else {
+            self.read_ptr()
+        }
+    }
 
-        unsafe { slice::from_raw_parts(ptr, len) }
+    /// Returns a reference to the contents of this string as a slice of bytes.
+    pub fn get_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data_ptr(), self.len()) }
     }
 
     /// Returns a mutable reference to the contents of this string as a slice of bytes.
     #[inline]
     pub fn get_bytes_mut(&mut self) -> &mut [u8] {
-        unsafe { &mut *(self.get_bytes() as *const [u8] as *mut [u8]) }
+        let len = self.len();
+        unsafe { slice::from_raw_parts_mut(self.data_ptr_mut(), len) }
     }
 
     /// Creates a new, empty, RawJavaString.
     pub const fn new() -> Self {
         Self {
             len: 0,
-            data: unsafe { NonNull::new_unchecked(usize::to_be(1) as *mut u8) },
+            data: unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(1)) },
         }
     }
 
-    /// Builds a new string from a vector of bytes.
-    ///
-    /// Doesn't perform any allocations/deallocations; if you hand in a vector
-    /// with more capacity than length, that capacity may be leaked when this
-    /// object is destroyed.
-    pub fn from_byte_vec(mut bytes: Vec<u8>) -> Self {
-        if bytes.len() <= Self::max_intern_len() {
-            Self::from_bytes(bytes)
+    /// Returns the pointer value marking a string as interned with length `len`.
+    /// The length lives in the pointer's low byte, next to the interned flag, so
+    /// anything longer than `max_intern_len()` would corrupt it.
+    #[inline(always)]
+    fn interned_ptr_value(len: usize) -> usize {
+        debug_assert!(
+            len <= Self::max_intern_len(),
+            "interned length {} doesn't fit in the length byte",
+            len
+        );
+        (len << 1) + 1
+    }
+
+    /// Builds a new string of `len` zero bytes, to be filled in through
+    /// `get_bytes_mut`. Takes at most one allocation.
+    pub fn zeroed(len: usize) -> Self {
+        let mut new = Self::new();
+        if len <= Self::max_intern_len() {
+            new.write_ptr(ptr::without_provenance_mut(Self::interned_ptr_value(len)));
         } else {
-            let mut new = Self::new();
-            new.len = bytes.len();
-            new.write_ptr(bytes.as_mut_ptr());
-            core::mem::forget(bytes);
-            new
+            use alloc::alloc::*;
+            let layout = unsafe { Layout::from_size_align_unchecked(len, 2) };
+            let ptr = unsafe { alloc_zeroed(layout) };
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+            new.len = len;
+            new.write_ptr(ptr);
         }
+        new
+    }
+
+    /// Builds a new string from a vector of bytes.
+    ///
+    /// The bytes are copied into a new buffer, since a `Vec<u8>`'s buffer
+    /// isn't guaranteed to be 2-aligned, and an odd pointer would be read as
+    /// an interned string. The vector's buffer is freed.
+    pub fn from_byte_vec(bytes: Vec<u8>) -> Self {
+        Self::from_bytes(&bytes[..])
     }
 
     /// Builds a new string from raw bytes.
@@ -136,30 +209,24 @@ impl RawJavaString {
     fn from_bytes_array_inline(
         bytes_list: impl Deref<Target = [impl Deref<Target = [u8]>]>,
     ) -> Self {
-        let mut new = Self::new();
-        let len = bytes_list
-            .iter()
-            .map(|bytes| bytes.len())
-            .fold(0, |sum, len| sum + len);
-
-        let (mut write_location, data_pointer_value) = if len <= Self::max_intern_len() {
-            let pointer_value = (len << 1) + 1;
-            (
-                (&mut new.len) as *mut usize as *mut u8,
-                (pointer_value as usize as *mut u8),
-            )
+        let len = bytes_list.iter().map(|bytes| bytes.len()).sum::<usize>();
+        let mut new = if len <= Self::max_intern_len() {
+            Self::zeroed(len)
         } else {
             use alloc::alloc::*;
-            // TODO use safe version and put this version behind flag
-            let ptr = unsafe { alloc(Layout::from_size_align_unchecked(len, 2)) };
+            let layout = unsafe { Layout::from_size_align_unchecked(len, 2) };
+            let ptr = unsafe { alloc(layout) };
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            let mut new = Self::new();
             new.len = len;
-            (ptr, ptr)
+            unsafe { new.write_ptr_unchecked(ptr) };
+            new
         };
 
-        unsafe {
-            new.write_ptr_unchecked(data_pointer_value);
-        }
-
+        let mut write_location = new.data_ptr_mut();
         for bytes in bytes_list.iter() {
             unsafe {
                 core::ptr::copy_nonoverlapping(bytes.as_ptr(), write_location, bytes.len());
@@ -170,10 +237,13 @@ impl RawJavaString {
         new
     }
 
+    /// Returns the contents of this string in a new `Vec`.
+    ///
+    /// The bytes are always copied: interned strings have no buffer to hand
+    /// over, and heap buffers are allocated with an alignment of 2, so giving
+    /// one to a `Vec<u8>` would free it with the wrong layout.
     pub fn into_bytes(self) -> Vec<u8> {
-        let out = unsafe { Vec::from_raw_parts(self.data.as_ptr(), self.len(), self.len()) };
-        mem::forget(self);
-        out
+        self.get_bytes().to_vec()
     }
 
     /// Overwrites what was previously in this buffer with the contents of bytes.
@@ -185,6 +255,13 @@ impl RawJavaString {
     }
 }
 
+// Safety: a `RawJavaString` uniquely owns its heap buffer, if it has one, just
+// like a `Box<[u8]>` would; clones get buffers of their own. The pointer is
+// never shared with anything else, and mutation needs `&mut self`, so moving
+// the string to another thread or reading it from several is sound.
+unsafe impl Send for RawJavaString {}
+unsafe impl Sync for RawJavaString {}
+
 impl Drop for RawJavaString {
     fn drop(&mut self) {
         if !self.is_interned() {
@@ -199,6 +276,13 @@ impl Drop for RawJavaString {
     }
 }
 
+impl Default for RawJavaString {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Clone for RawJavaString {
     #[inline(always)]
     fn clone(&self) -> Self {
@@ -229,10 +313,45 @@ impl DerefMut for RawJavaString {
 
 impl PartialEq for RawJavaString {
     fn eq(&self, other: &Self) -> bool {
-        &*self == &*other
+        // Strings are interned exactly when they're short, so comparing lengths
+        // also rules out an interned string equalling a heap one, and neither
+        // needs to touch the bytes.
+        if self.len() != other.len() {
+            return false;
+        }
+        self.get_bytes() == other.get_bytes()
     }
 }
 
+impl Hash for RawJavaString {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_bytes().hash(state)
+    }
+}
+
+/// Implements byte equality between `RawJavaString` and `$bytes`, in both
+/// orders.
+macro_rules! impl_eq_bytes {
+    ($bytes:ty) => {
+        impl PartialEq<$bytes> for RawJavaString {
+            fn eq(&self, rhs: &$bytes) -> bool {
+                self.get_bytes() == &rhs[..]
+            }
+        }
+
+        impl PartialEq<RawJavaString> for $bytes {
+            fn eq(&self, rhs: &RawJavaString) -> bool {
+                &self[..] == rhs.get_bytes()
+            }
+        }
+    };
+}
+
+impl_eq_bytes!([u8]);
+impl_eq_bytes!(&[u8]);
+impl_eq_bytes!(Vec<u8>);
+
 #[cfg(test)]
 mod tests {
 
@@ -244,6 +363,22 @@ mod tests {
         assert!(string.is_interned(), "Empty RawJavaString isn't interned!");
     }
 
+    #[test]
+    fn default_is_empty() {
+        let string = RawJavaString::default();
+        assert!(string.is_interned());
+        assert_eq!(string.len(), 0);
+
+        // Taking a heap string leaves an empty one behind, and each buffer is
+        // freed exactly once; miri checks the latter.
+        let mut heap = RawJavaString::from_bytes(&[b'x'; 100][..]);
+        let taken = mem::take(&mut heap);
+        assert!(heap.is_interned());
+        assert_eq!(taken.len(), 100);
+        drop(taken);
+        drop(heap);
+    }
+
     #[test]
     fn option_size() {
         assert!(
@@ -350,4 +485,89 @@ mod tests {
             string
         );
     }
+
+    #[test]
+    fn unequal_lengths_skip_bytes() {
+        // A heap string whose pointer can't be read from; comparing it would
+        // crash if the bytes were ever touched.
+        let mut bogus = mem::ManuallyDrop::new(RawJavaString::new());
+        bogus.len = 1000;
+        bogus.write_ptr(ptr::without_provenance_mut(mem::align_of::<u16>()));
+        assert!(!bogus.is_interned());
+
+        let interned = RawJavaString::from_bytes(&b"short"[..]);
+        let heap = RawJavaString::from_bytes(&[0; 999][..]);
+        assert!(*bogus != interned);
+        assert!(interned != *bogus);
+        assert!(*bogus != heap);
+        assert!(heap != *bogus);
+    }
+
+    #[test]
+    fn interned_length_encoding() {
+        for len in 0..=RawJavaString::max_intern_len() {
+            let string = RawJavaString::from_bytes(&[b'a'; 32][..len]);
+            assert!(string.is_interned());
+            assert_eq!(string.len(), len);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "doesn't fit in the length byte")]
+    fn interned_length_overflow() {
+        RawJavaString::interned_ptr_value(RawJavaString::max_intern_len() + 1);
+    }
+
+    #[test]
+    fn into_bytes() {
+        // Run under miri to check the heap buffer is freed with its own layout.
+        let long: &[u8] = &[b'x'; 100];
+        let bytes = RawJavaString::from_bytes(long).into_bytes();
+        assert_eq!(bytes, long);
+        drop(bytes);
+
+        let short: &[u8] = b"short";
+        let mut bytes = RawJavaString::from_bytes(short).into_bytes();
+        assert_eq!(bytes, short);
+        bytes.push(b'!');
+        assert_eq!(bytes, b"short!");
+    }
+
+    #[test]
+    fn from_bytes_array_mixed_lengths() {
+        let cases: &[&[&[u8]]] = &[
+            &[],
+            &[b"", b""],
+            &[b"a", b"", b"bcd", b"ef"],
+            &[b"", b"0123456789", b"", b"abcd", b"e"],
+            &[
+                b"abc",
+                b"",
+                b"a much longer slice that forces the heap",
+                b"",
+                b"!",
+            ],
+            &[b"", b"0123456789abcdef", b""],
+        ];
+
+        for slices in cases {
+            let expected = slices.concat();
+            let string = RawJavaString::from_bytes_array(*slices);
+            assert_eq!(string.get_bytes(), &expected[..]);
+            assert_eq!(
+                string.is_interned(),
+                expected.len() <= RawJavaString::max_intern_len()
+            );
+        }
+    }
+
+    #[test]
+    fn hash_matches_bytes() {
+        use crate::tests::hash_of;
+
+        for bytes in &[&b""[..], b"short", &[b'x'; 100]] {
+            assert_eq!(hash_of(&RawJavaString::from_bytes(*bytes)), hash_of(*bytes));
+        }
+    }
 }
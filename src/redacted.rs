@@ -0,0 +1,158 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// Wrapper that keeps a sensitive string out of logs.
+///
+/// `Display` always prints `***`, and `Debug` prints a masked preview made of
+/// the first and last characters and the length (or just `***` if the preview
+/// is turned off). Serializing also writes `***` unless passthrough has been
+/// turned on with [`serialize_exposed`].
+///
+/// The only way to read the contents is [`expose_secret`].
+///
+/// # Examples
+///
+/// ```
+/// # use jstring::{JavaString, Redacted};
+/// let email = Redacted::new(JavaString::from("alice@example.com"));
+///
+/// assert_eq!(format!("{}", email), "***");
+/// assert_eq!(format!("{:?}", email), "\"a***m\" (17 chars)");
+/// assert_eq!(email.expose_secret(), "alice@example.com");
+/// ```
+///
+/// [`serialize_exposed`]: struct.Redacted.html#method.serialize_exposed
+/// [`expose_secret`]: struct.Redacted.html#method.expose_secret
+#[derive(Clone, PartialEq, Eq)]
+pub struct Redacted<T> {
+    value: T,
+    preview: bool,
+    passthrough: bool,
+}
+
+impl<T: Deref<Target = str>> Redacted<T> {
+    /// Wraps `value`, showing a masked preview in `Debug` output.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            preview: true,
+            passthrough: false,
+        }
+    }
+
+    /// Wraps `value`, showing nothing but `***` in `Debug` output.
+    pub const fn without_preview(value: T) -> Self {
+        Self {
+            value,
+            preview: false,
+            passthrough: false,
+        }
+    }
+
+    /// Sets whether serializing this value writes the real contents instead of
+    /// `***`. Off by default.
+    pub fn serialize_exposed(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Returns the wrapped contents.
+    pub fn expose_secret(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        formatter.write_str("***")
+    }
+}
+
+impl<T: Deref<Target = str>> fmt::Debug for Redacted<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if !self.preview {
+            return formatter.write_str("***");
+        }
+
+        let count = self.value.chars().count();
+        let mut chars = self.value.chars();
+        match (chars.next(), chars.next_back()) {
+            (Some(first), Some(last)) if count > 2 => {
+                write!(formatter, "\"{}***{}\" ({} chars)", first, last, count)
+            }
+            _ => write!(formatter, "\"***\" ({} chars)", count),
+        }
+    }
+}
+
+impl<T: Deref<Target = str>> serde::Serialize for Redacted<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.passthrough {
+            self.value.serialize(serializer)
+        } else {
+            "***".serialize(serializer)
+        }
+    }
+}
+
+impl<'de, T> serde::Deserialize<'de> for Redacted<T>
+where
+    T: Deref<Target = str> + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self::new(T::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::JavaString;
+
+    #[test]
+    fn display_is_fully_redacted() {
+        let token = Redacted::new(JavaString::from("hunter2"));
+        assert_eq!(format!("{}", token), "***");
+        assert_eq!(format!("{:>10}", token), "***");
+    }
+
+    #[test]
+    fn debug_preview() {
+        let heap = Redacted::new(JavaString::from("tok_1234567890abcdefghij"));
+        assert_eq!(format!("{:?}", heap), "\"t***j\" (24 chars)");
+
+        let multibyte = Redacted::new(JavaString::from("💖secret💖"));
+        assert_eq!(format!("{:?}", multibyte), "\"💖***💖\" (8 chars)");
+
+        let short = Redacted::new(JavaString::from("ab"));
+        assert_eq!(format!("{:?}", short), "\"***\" (2 chars)");
+
+        let hidden = Redacted::without_preview(JavaString::from("hunter2"));
+        assert_eq!(format!("{:?}", hidden), "***");
+    }
+
+    #[test]
+    fn serialize_redacted_by_default() {
+        let token = Redacted::new(JavaString::from("hunter2"));
+        assert_eq!(serde_json::to_string(&token).unwrap(), "\"***\"");
+
+        let token = token.serialize_exposed(true);
+        assert_eq!(serde_json::to_string(&token).unwrap(), "\"hunter2\"");
+    }
+
+    #[test]
+    fn expose_secret() {
+        let token: Redacted<JavaString> = serde_json::from_str("\"hunter2\"").unwrap();
+        assert_eq!(token.expose_secret(), "hunter2");
+
+        let borrowed = Redacted::new("hunter2");
+        assert_eq!(borrowed.expose_secret(), "hunter2");
+    }
+}
@@ -0,0 +1,137 @@
+//! Truncation that respects extended grapheme clusters, so user-perceived
+//! characters like 👨‍👩‍👧 or 🇫🇷 are never cut in half.
+
+use crate::JavaString;
+use unicode_segmentation::UnicodeSegmentation;
+
+impl JavaString {
+    /// Returns the byte length of the first `n` extended grapheme clusters,
+    /// or `None` if there are no more than `n` of them.
+    fn graphemes_len(&self, n: usize) -> Option<usize> {
+        self.grapheme_indices(true).nth(n).map(|(idx, _)| idx)
+    }
+
+    /// Shortens this `JavaString` to at most `max_graphemes` extended grapheme
+    /// clusters.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let mut s = JavaString::from("👨‍👩‍👧 family");
+    ///
+    /// s.truncate_graphemes(1);
+    /// assert_eq!(s, "👨‍👩‍👧");
+    /// ```
+    pub fn truncate_graphemes(&mut self, max_graphemes: usize) {
+        if let Some(len) = self.graphemes_len(max_graphemes) {
+            self.truncate(len);
+        }
+    }
+
+    /// Returns a new `JavaString` holding at most the first `n` extended
+    /// grapheme clusters of this one.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("🇫🇷🇩🇪🇯🇵");
+    ///
+    /// assert_eq!(s.take_graphemes(2), "🇫🇷🇩🇪");
+    /// ```
+    pub fn take_graphemes(&self, n: usize) -> JavaString {
+        match self.graphemes_len(n) {
+            Some(len) => JavaString::from(&self[..len]),
+            None => self.clone(),
+        }
+    }
+
+    /// Returns the first `n` extended grapheme clusters of this `JavaString`,
+    /// followed by `ellipsis` if anything was cut off. The ellipsis isn't
+    /// counted towards `n`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::JavaString;
+    /// let s = JavaString::from("naïve café");
+    ///
+    /// assert_eq!(s.ellipsize_graphemes(5, "…"), "naïve…");
+    /// assert_eq!(s.ellipsize_graphemes(10, "…"), "naïve café");
+    /// ```
+    pub fn ellipsize_graphemes(&self, n: usize, ellipsis: &str) -> JavaString {
+        match self.graphemes_len(n) {
+            Some(len) => JavaString::concat_pair(&self[..len], ellipsis),
+            None => self.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SAMPLES: &[&str] = &[
+        "👨‍👩‍👧👩‍❤️‍👨 zwj",
+        "🇫🇷🇩🇪🇯🇵🇺🇸",
+        "e\u{301}a\u{308}\u{323}o\u{302} combining",
+        "plain ascii text that lives on the heap",
+        "",
+    ];
+
+    fn count(text: &str) -> usize {
+        text.graphemes(true).count()
+    }
+
+    #[test]
+    fn never_splits_clusters() {
+        for text in SAMPLES {
+            let total = count(text);
+            let string = JavaString::from(*text);
+
+            for n in 0..=total + 1 {
+                let taken = string.take_graphemes(n);
+                assert_eq!(count(&taken), n.min(total));
+                assert!(text.starts_with(taken.as_str()));
+                assert!(text.graphemes(true).take(n).eq(taken.graphemes(true)));
+
+                let mut truncated = string.clone();
+                truncated.truncate_graphemes(n);
+                assert_eq!(truncated, taken);
+
+                let ellipsized = string.ellipsize_graphemes(n, "...");
+                if n < total {
+                    assert_eq!(ellipsized, *format!("{}...", taken));
+                } else {
+                    assert_eq!(ellipsized, string);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cluster_boundaries() {
+        let family = JavaString::from("👨‍👩‍👧👩‍❤️‍👨 zwj");
+        assert_eq!(family.take_graphemes(1), "👨‍👩‍👧");
+        assert_eq!(family.ellipsize_graphemes(2, "…"), "👨‍👩‍👧👩‍❤️‍👨…");
+
+        let flags = JavaString::from("🇫🇷🇩🇪🇯🇵🇺🇸");
+        assert_eq!(flags.take_graphemes(3), "🇫🇷🇩🇪🇯🇵");
+
+        let mut accents = JavaString::from("e\u{301}a\u{308}\u{323}o\u{302} combining");
+        accents.truncate_graphemes(2);
+        assert_eq!(accents, "e\u{301}a\u{308}\u{323}");
+
+        accents.truncate_graphemes(0);
+        assert_eq!(accents, "");
+        assert_eq!(flags.ellipsize_graphemes(0, "…"), "…");
+    }
+}
@@ -0,0 +1,250 @@
+//! Sorting helpers for large slices of `JavaString`.
+//!
+//! Both sorts produce exactly the same order as `sort_unstable()`, i.e. the
+//! byte-wise order of the strings' contents. Neither is stable, which only
+//! matters if you can tell equal strings apart, e.g. by their addresses.
+
+use crate::JavaString;
+
+/// Buckets with at most this many strings are finished with a comparison sort.
+const SMALL_BUCKET: usize = 32;
+
+/// Number of radix buckets; bucket 0 holds strings that have run out of bytes.
+const BUCKETS: usize = 257;
+
+/// Sorts `strings` with an MSD radix sort over their bytes.
+///
+/// Each pass reads a single byte from each string, which for interned strings
+/// lives inside the `JavaString` itself, so short keys never touch the heap.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// # use jstring::JavaString;
+/// use jstring::sort::sort_java_strings;
+///
+/// let mut strings: Vec<JavaString> = vec!["pear".into(), "apple".into(), "fig".into()];
+/// sort_java_strings(&mut strings);
+///
+/// assert_eq!(strings, ["apple", "fig", "pear"]);
+/// ```
+pub fn sort_java_strings(strings: &mut [JavaString]) {
+    msd_sort(strings, 0);
+}
+
+/// Sorts `strings` by a cached `u64` made of the first 8 bytes of each string,
+/// only comparing the full contents of strings whose cached keys are equal.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// # use jstring::JavaString;
+/// use jstring::sort::sort_by_cached_prefix;
+///
+/// let mut strings: Vec<JavaString> = vec!["pear".into(), "apple".into(), "fig".into()];
+/// sort_by_cached_prefix(&mut strings);
+///
+/// assert_eq!(strings, ["apple", "fig", "pear"]);
+/// ```
+pub fn sort_by_cached_prefix(strings: &mut [JavaString]) {
+    let mut keyed: Vec<(u64, JavaString)> = strings
+        .iter_mut()
+        .map(|string| (prefix_key(string), core::mem::take(string)))
+        .collect();
+
+    keyed.sort_unstable_by(|(a_key, a), (b_key, b)| {
+        a_key
+            .cmp(b_key)
+            .then_with(|| a.as_bytes().cmp(b.as_bytes()))
+    });
+
+    for (slot, (_, string)) in strings.iter_mut().zip(keyed) {
+        *slot = string;
+    }
+}
+
+/// Packs the first 8 bytes of `string` into a big-endian `u64`, padding with
+/// zeroes. A string that runs out of bytes sorts before any string it's a
+/// prefix of, so differing keys always agree with the order of the strings.
+fn prefix_key(string: &JavaString) -> u64 {
+    let bytes = string.as_bytes();
+    let mut key = [0; 8];
+    let len = bytes.len().min(8);
+    key[0..len].copy_from_slice(&bytes[0..len]);
+    u64::from_be_bytes(key)
+}
+
+#[inline(always)]
+fn bucket(string: &JavaString, depth: usize) -> usize {
+    string.as_bytes().get(depth).map_or(0, |&b| b as usize + 1)
+}
+
+/// Sorts `strings`, all of which share their first `depth` bytes.
+fn msd_sort(mut strings: &mut [JavaString], mut depth: usize) {
+    loop {
+        if strings.len() <= SMALL_BUCKET {
+            strings.sort_unstable_by(|a, b| a.as_bytes()[depth..].cmp(&b.as_bytes()[depth..]));
+            return;
+        }
+
+        let mut counts = [0; BUCKETS];
+        for string in strings.iter() {
+            counts[bucket(string, depth)] += 1;
+        }
+
+        // Everything shares the next byte too, so skip ahead without recursing.
+        // This keeps the stack shallow for long common prefixes.
+        if counts.contains(&strings.len()) {
+            if counts[0] == strings.len() {
+                return;
+            }
+            depth += 1;
+            continue;
+        }
+
+        let mut starts = [0; BUCKETS];
+        for b in 1..BUCKETS {
+            starts[b] = starts[b - 1] + counts[b - 1];
+        }
+
+        // American flag sort: swap every string into its bucket in place.
+        let mut next = starts;
+        for b in 0..BUCKETS {
+            let end = starts[b] + counts[b];
+            while next[b] < end {
+                let target = bucket(&strings[next[b]], depth);
+                if target == b {
+                    next[b] += 1;
+                } else {
+                    strings.swap(next[b], next[target]);
+                    next[target] += 1;
+                }
+            }
+        }
+
+        // Bucket 0 holds strings equal to the shared prefix, so it's already
+        // sorted. Recurse into every bucket but the largest, which we loop on.
+        let largest = (1..BUCKETS).max_by_key(|&b| counts[b]).unwrap();
+        for b in 1..BUCKETS {
+            if b != largest && counts[b] > 1 {
+                msd_sort(&mut strings[starts[b]..starts[b] + counts[b]], depth + 1);
+            }
+        }
+
+        strings = &mut strings[starts[largest]..starts[largest] + counts[largest]];
+        depth += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::XorShift;
+
+    fn random_corpus(
+        rng: &mut XorShift,
+        count: usize,
+        max_len: usize,
+        alphabet: &[&str],
+    ) -> Vec<JavaString> {
+        (0..count)
+            .map(|_| {
+                let len = rng.next() % (max_len + 1);
+                (0..len)
+                    .map(|_| alphabet[rng.next() % alphabet.len()])
+                    .collect::<String>()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn assert_sorts_like_std(strings: Vec<JavaString>) {
+        let mut expected = strings.clone();
+        expected.sort_unstable();
+
+        let mut radix = strings.clone();
+        sort_java_strings(&mut radix);
+        assert_eq!(radix, expected);
+
+        let mut cached = strings;
+        sort_by_cached_prefix(&mut cached);
+        assert_eq!(cached, expected);
+    }
+
+    #[test]
+    fn random_corpora() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        let narrow = &["a", "b", "c"];
+        let wide = &["a", "z", "\0", "é", "€", "💖", "\u{7f}"];
+        let large = if cfg!(miri) { 100 } else { 1_000 };
+
+        for &(count, max_len) in &[
+            (0, 4),
+            (1, 4),
+            (10, 4),
+            (large, 4),
+            (large, 40),
+            (large * 5, 12),
+        ] {
+            assert_sorts_like_std(random_corpus(&mut rng, count, max_len, narrow));
+            assert_sorts_like_std(random_corpus(&mut rng, count, max_len, wide));
+        }
+    }
+
+    #[test]
+    fn shared_prefixes() {
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        let prefix = "a long shared prefix that lives on the heap ";
+        let count = if cfg!(miri) { 200 } else { 2_000 };
+        let strings = random_corpus(&mut rng, count, 6, &["x", "y", ""])
+            .into_iter()
+            .map(|suffix| JavaString::from(format!("{}{}", prefix, suffix)))
+            .collect();
+
+        assert_sorts_like_std(strings);
+    }
+
+    #[test]
+    fn all_equal() {
+        let count = if cfg!(miri) { 100 } else { 1_000 };
+        assert_sorts_like_std(vec![JavaString::from("same"); count]);
+        assert_sorts_like_std(vec![
+            JavaString::from("the same string, on the heap");
+            count
+        ]);
+        assert_sorts_like_std(vec![JavaString::new(); count]);
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_million_short_keys() {
+        use std::time::Instant;
+
+        let mut rng = XorShift(0x853c_49e6_748f_ea9b);
+        let alphabet = &["a", "b", "c", "d", "e", "f", "g", "h"];
+        let strings = random_corpus(&mut rng, 1_000_000, 12, alphabet);
+
+        let mut expected = strings.clone();
+        let start = Instant::now();
+        expected.sort_unstable();
+        println!("sort_unstable:         {:?}", start.elapsed());
+
+        let mut radix = strings.clone();
+        let start = Instant::now();
+        sort_java_strings(&mut radix);
+        println!("sort_java_strings:     {:?}", start.elapsed());
+
+        let mut cached = strings;
+        let start = Instant::now();
+        sort_by_cached_prefix(&mut cached);
+        println!("sort_by_cached_prefix: {:?}", start.elapsed());
+
+        assert_eq!(radix, expected);
+        assert_eq!(cached, expected);
+    }
+}
@@ -0,0 +1,232 @@
+//! A companion to `JavaString` for platform strings that might not be valid
+//! Unicode.
+//!
+//! `JavaOsString` stores the same bytes as [`OsStr::as_encoded_bytes`], which
+//! are WTF-8 on Windows and the raw bytes on Unix, in the same compact layout
+//! as `JavaString`. Short strings are interned just like they are there.
+//!
+//! [`OsStr::as_encoded_bytes`]: https://doc.rust-lang.org/std/ffi/struct.OsStr.html#method.as_encoded_bytes
+
+use crate::raw_string::RawJavaString;
+use crate::JavaString;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// An owned, compact platform string, which can hold anything an [`OsString`]
+/// can, including unpaired surrogates on Windows and non-UTF-8 bytes on Unix.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// # use jstring::wtf8::JavaOsString;
+/// use std::ffi::OsString;
+///
+/// let name = JavaOsString::from(OsString::from("Cargo.toml"));
+///
+/// assert_eq!(name, *"Cargo.toml");
+/// assert_eq!(OsString::from(name), "Cargo.toml");
+/// ```
+///
+/// [`OsString`]: https://doc.rust-lang.org/std/ffi/struct.OsString.html
+#[derive(Clone, PartialEq, Eq)]
+pub struct JavaOsString {
+    data: RawJavaString,
+}
+
+impl JavaOsString {
+    /// Creates a new empty `JavaOsString`.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            data: RawJavaString::new(),
+        }
+    }
+
+    /// Returns the contents of this `JavaOsString` as an `OsStr`.
+    #[inline(always)]
+    pub fn as_os_str(&self) -> &OsStr {
+        // Safety: the bytes were copied from `OsStr::as_encoded_bytes`, or
+        // from a `JavaString`, which is valid UTF-8.
+        unsafe { OsStr::from_encoded_bytes_unchecked(self.data.get_bytes()) }
+    }
+
+    /// Converts this `JavaOsString` into a `JavaString` without copying.
+    ///
+    /// # Errors
+    ///
+    /// Returns the original `JavaOsString` if it isn't valid Unicode.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use jstring::wtf8::JavaOsString;
+    /// # use jstring::JavaString;
+    /// let name = JavaOsString::from(JavaString::from("Cargo.toml"));
+    ///
+    /// assert_eq!(name.into_java_string().unwrap(), "Cargo.toml");
+    /// ```
+    pub fn into_java_string(self) -> Result<JavaString, JavaOsString> {
+        if core::str::from_utf8(self.data.get_bytes()).is_ok() {
+            Ok(JavaString { data: self.data })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Default for JavaOsString {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for JavaOsString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Debug::fmt(self.as_os_str(), formatter)
+    }
+}
+
+impl Deref for JavaOsString {
+    type Target = OsStr;
+    fn deref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl AsRef<OsStr> for JavaOsString {
+    fn as_ref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl AsRef<Path> for JavaOsString {
+    fn as_ref(&self) -> &Path {
+        Path::new(self.as_os_str())
+    }
+}
+
+impl Borrow<OsStr> for JavaOsString {
+    fn borrow(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl From<&OsStr> for JavaOsString {
+    fn from(string: &OsStr) -> Self {
+        Self {
+            data: RawJavaString::from_bytes(string.as_encoded_bytes()),
+        }
+    }
+}
+
+impl From<OsString> for JavaOsString {
+    fn from(string: OsString) -> Self {
+        Self::from(string.as_os_str())
+    }
+}
+
+impl From<JavaString> for JavaOsString {
+    fn from(string: JavaString) -> Self {
+        Self { data: string.data }
+    }
+}
+
+impl From<JavaOsString> for OsString {
+    fn from(string: JavaOsString) -> Self {
+        string.as_os_str().to_os_string()
+    }
+}
+
+impl Hash for JavaOsString {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.as_os_str().hash(hasher)
+    }
+}
+
+impl PartialEq<OsStr> for JavaOsString {
+    fn eq(&self, other: &OsStr) -> bool {
+        self.as_os_str() == other
+    }
+}
+
+impl PartialEq<str> for JavaOsString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_os_str() == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::hash_of;
+    use core::mem;
+
+    fn round_trip(original: OsString) {
+        let string = JavaOsString::from(original.clone());
+        assert_eq!(string, *original);
+        assert_eq!(hash_of(&string), hash_of(original.as_os_str()));
+        assert_eq!(OsString::from(string.clone()), original);
+
+        let result = string.into_java_string();
+        assert_eq!(result.is_ok(), original.to_str().is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+
+        round_trip(OsStr::from_bytes(b"caf\xe9").to_os_string());
+        round_trip(OsStr::from_bytes(b"a path that lives on the heap \xff\xfe").to_os_string());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn unpaired_surrogate_round_trip() {
+        use std::os::windows::ffi::OsStringExt;
+
+        round_trip(OsString::from_wide(&[0x66, 0xD800, 0x6f]));
+        let mut wide: Vec<u16> = "a path that lives on the heap ".encode_utf16().collect();
+        wide.push(0xDC00);
+        round_trip(OsString::from_wide(&wide));
+    }
+
+    #[test]
+    fn unicode_round_trip() {
+        round_trip(OsString::new());
+        round_trip(OsString::from("héllo💖"));
+        round_trip(OsString::from("a path that lives on the heap"));
+
+        let string = JavaOsString::from(JavaString::from("héllo"));
+        assert_eq!(string.into_java_string().unwrap(), "héllo");
+    }
+
+    #[test]
+    fn interning() {
+        let max = RawJavaString::max_intern_len();
+        let short = JavaOsString::from(OsString::from("a".repeat(max)));
+        assert!(short.data.is_interned());
+
+        let long = JavaOsString::from(OsString::from("a".repeat(max + 1)));
+        assert!(!long.data.is_interned());
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(mem::size_of::<JavaOsString>(), 2 * mem::size_of::<usize>());
+        assert_eq!(
+            mem::size_of::<Option<JavaOsString>>(),
+            mem::size_of::<JavaString>()
+        );
+    }
+}